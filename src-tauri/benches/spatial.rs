@@ -0,0 +1,94 @@
+//! Spatial grid queries against a linear scan, at map sizes well beyond the
+//! bundled ones. Run with `cargo bench --bench spatial`.
+
+use std::hint::black_box;
+use std::time::Instant;
+use the_gatherer_lib::spatial::SpatialGrid;
+use the_gatherer_lib::vector2::Vector2;
+
+const QUERIES: usize = 2_000;
+
+/// Deterministic positions, so runs compare against each other.
+fn positions(count: usize, extent: f64) -> Vec<Vector2> {
+    let mut state: u64 = 0x9E37_79B9_7F4A_7C15;
+    let mut next = move || {
+        state ^= state << 13;
+        state ^= state >> 7;
+        state ^= state << 17;
+        (state >> 11) as f64 / (1u64 << 53) as f64 * extent
+    };
+    (0..count).map(|_| Vector2::new(next(), next())).collect()
+}
+
+fn time(label: &str, count: usize, mut query: impl FnMut(usize) -> usize) {
+    let start = Instant::now();
+    let mut found = 0;
+    for index in 0..QUERIES {
+        found += query(index);
+    }
+    let per_query = start.elapsed() / QUERIES as u32;
+    println!(
+        "{:>7} nodes  {:<24} {:>10.2?}/query  ({} found)",
+        count,
+        label,
+        per_query,
+        black_box(found)
+    );
+}
+
+fn main() {
+    for count in [10_000, 100_000] {
+        // Keeps the density of the bundled 3000x3000 map at 100 nodes.
+        let extent = 3000.0 * (count as f64 / 100.0).sqrt();
+        let nodes = positions(count, extent);
+        let centers = positions(QUERIES, extent);
+        let mut grid = SpatialGrid::default();
+        for (key, position) in nodes.iter().enumerate() {
+            grid.insert(key, *position);
+        }
+        let viewport = Vector2::new(640.0, 360.0);
+
+        time("query_rect (viewport)", count, |i| {
+            grid.query_rect(centers[i] - viewport, centers[i] + viewport)
+                .len()
+        });
+        time("scan_rect (viewport)", count, |i| {
+            let (min, max) = (centers[i] - viewport, centers[i] + viewport);
+            nodes
+                .iter()
+                .filter(|p| p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y)
+                .count()
+        });
+        time("query_radius (400)", count, |i| {
+            grid.query_radius(centers[i], 400.0).len()
+        });
+        time("scan_radius (400)", count, |i| {
+            nodes
+                .iter()
+                .filter(|p| p.distance_to(centers[i]) <= 400.0)
+                .count()
+        });
+        time("nearest", count, |i| {
+            usize::from(grid.nearest(centers[i], |_| true).is_some())
+        });
+        time("nearest (1 in 100)", count, |i| {
+            usize::from(
+                grid.nearest(centers[i], |key| key.is_multiple_of(100))
+                    .is_some(),
+            )
+        });
+        time("scan_nearest (1 in 100)", count, |i| {
+            let nearest = nodes
+                .iter()
+                .enumerate()
+                .filter(|(key, _)| key.is_multiple_of(100))
+                .map(|(_, p)| p.distance_to(centers[i]))
+                .min_by(|a, b| a.total_cmp(b));
+            usize::from(black_box(nearest).is_some())
+        });
+        time("nearest (far away)", count, |i| {
+            let far = centers[i] + Vector2::new(1e12, 1e12);
+            usize::from(grid.nearest(far, |_| true).is_some())
+        });
+    }
+}
@@ -1,3 +1,5 @@
 fn main() {
+    // Headless builds have no app context to generate.
+    #[cfg(feature = "gui")]
     tauri_build::build()
 }
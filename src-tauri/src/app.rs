@@ -0,0 +1,270 @@
+use crate::map::MapState;
+use crate::startup::{
+    StartupRegistry, StartupStatus, TaskProgress, DEFAULT_STARTUP_TIMEOUT, ERROR_TASK_FAILED,
+    STARTUP_PROGRESS_EVENT, STARTUP_RETRY_EVENT,
+};
+use crate::world::World;
+use crate::{
+    crafting, discovery, generator, init, map, pathfinding, profile, save, sync, tick, tiers,
+    tools, world,
+};
+use std::sync::Mutex;
+use tauri::async_runtime::spawn;
+use tauri::{AppHandle, Emitter, Manager, State, WindowEvent};
+use tokio::time::{sleep, Duration};
+
+/// Overall startup timeout in seconds, overridable for slow machines.
+const STARTUP_TIMEOUT_ENV: &str = "GATHERER_STARTUP_TIMEOUT";
+
+#[cfg_attr(mobile, tauri::mobile_entry_point)]
+pub fn run() {
+    let timeout = std::env::var(STARTUP_TIMEOUT_ENV)
+        .ok()
+        .and_then(|secs| secs.parse().ok())
+        .map(Duration::from_secs)
+        .unwrap_or(DEFAULT_STARTUP_TIMEOUT);
+    let mut registry = StartupRegistry::new(timeout);
+    registry
+        .register("backend", Vec::new(), true)
+        .expect("failed to register backend startup task");
+    registry
+        .register("frontend", Vec::new(), true)
+        .expect("failed to register frontend startup task");
+
+    tauri::Builder::default()
+        .manage(Mutex::new(registry))
+        .manage(Mutex::new(World::new()))
+        .manage(Mutex::new(MapState::default()))
+        .manage(Mutex::new(sync::SyncEncoder::default()))
+        .manage(Mutex::new(discovery::LanSessions::default()))
+        .invoke_handler(tauri::generate_handler![
+            greet,
+            register_task,
+            set_progress,
+            set_complete,
+            set_failed,
+            retry_startup_task,
+            get_startup_status,
+            quit_app,
+            map::get_map,
+            map::load_map,
+            generator::generate_map,
+            profile::get_profile,
+            profile::update_profile,
+            save::save_game,
+            save::load_game,
+            world::join_world,
+            world::leave_world,
+            world::set_player_position,
+            world::move_player,
+            world::start_gathering,
+            world::stop_gathering,
+            world::get_capacity,
+            world::deposit,
+            world::withdraw,
+            world::bank_contents,
+            world::query_radius,
+            world::query_rect,
+            world::nearest_of_tier,
+            world::gatherable_resources,
+            crafting::list_recipes,
+            crafting::can_craft,
+            crafting::craft,
+            tools::list_tools,
+            tools::get_gather_stats,
+            tiers::get_tier_definitions,
+            pathfinding::find_path,
+            sync::ack_sync,
+            sync::request_resync,
+            discovery::list_lan_sessions,
+            world::heartbeat,
+            world::get_world_snapshot
+        ])
+        .setup(|app| {
+            spawn(setup(app.handle().clone()));
+            spawn(watch_startup_timeout(app.handle().clone()));
+            spawn(tick::run(app.handle().clone()));
+            spawn(save::run_autosave(app.handle().clone()));
+            spawn(discovery::run(app.handle().clone()));
+            Ok(())
+        })
+        .on_window_event(|window, event| {
+            if window.label() == "main" && matches!(event, WindowEvent::CloseRequested { .. }) {
+                if let Err(e) = save::save_local(window.app_handle()) {
+                    println!("Failed to save on close: {}", e);
+                }
+            }
+        })
+        .run(tauri::generate_context!())
+        .expect("error while running tauri application");
+}
+
+#[tauri::command]
+fn greet(name: &str) -> String {
+    format!("Hello, {}! You've been greeted from Rust!", name)
+}
+
+#[tauri::command]
+fn register_task(
+    state: State<'_, Mutex<StartupRegistry>>,
+    task: String,
+    depends_on: Option<Vec<String>>,
+    required: Option<bool>,
+) -> Result<(), String> {
+    let mut registry = state.lock().unwrap();
+    registry.register(&task, depends_on.unwrap_or_default(), required.unwrap_or(true))
+}
+
+#[tauri::command]
+fn set_progress(
+    app: AppHandle,
+    state: State<'_, Mutex<StartupRegistry>>,
+    task: String,
+    percent: u8,
+    step: Option<String>,
+) -> Result<(), String> {
+    let progress = state.lock().unwrap().update(&task, percent, step)?;
+    emit_progress(&app, progress);
+    Ok(())
+}
+
+#[tauri::command]
+async fn set_complete(
+    app: AppHandle,
+    state: State<'_, Mutex<StartupRegistry>>,
+    task: String,
+) -> Result<(), String> {
+    let (progress, just_finished) = {
+        let mut registry = state.lock().unwrap();
+        let was_finished = registry.is_finished();
+        let progress = registry.complete(&task)?;
+        (progress, !was_finished && registry.is_finished())
+    };
+    emit_progress(&app, progress);
+    // Optional tasks may complete after the splashscreen is already gone.
+    if just_finished {
+        finish_startup(&app)?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn set_failed(
+    app: AppHandle,
+    state: State<'_, Mutex<StartupRegistry>>,
+    task: String,
+    code: Option<String>,
+    message: String,
+) -> Result<(), String> {
+    let code = code.unwrap_or_else(|| ERROR_TASK_FAILED.to_string());
+    let progress = state.lock().unwrap().fail(&task, &code, message)?;
+    emit_progress(&app, progress);
+    Ok(())
+}
+
+/// Resets a failed task. Backend tasks are re-run here, frontend tasks are
+/// asked to run again through the `startup-retry` event on the main window.
+#[tauri::command]
+fn retry_startup_task(
+    app: AppHandle,
+    state: State<'_, Mutex<StartupRegistry>>,
+    task: String,
+) -> Result<(), String> {
+    let progress = state.lock().unwrap().retry(&task)?;
+    emit_progress(&app, progress);
+    if task == "backend" {
+        spawn(setup(app.clone()));
+    } else {
+        app.emit_to("main", STARTUP_RETRY_EVENT, task)
+            .map_err(|e| format!("Failed to request retry: {}", e))?;
+    }
+    Ok(())
+}
+
+#[tauri::command]
+fn get_startup_status(state: State<'_, Mutex<StartupRegistry>>) -> StartupStatus {
+    state.lock().unwrap().status()
+}
+
+#[tauri::command]
+fn quit_app(app: AppHandle) {
+    app.exit(1);
+}
+
+fn emit_progress(app: &AppHandle, progress: TaskProgress) {
+    if let Err(e) = app.emit_to("splashscreen", STARTUP_PROGRESS_EVENT, progress) {
+        println!("Failed to emit startup progress: {}", e);
+    }
+}
+
+fn report_step(app: &AppHandle, task: &str, percent: u8, step: &str) {
+    let progress = app
+        .state::<Mutex<StartupRegistry>>()
+        .lock()
+        .unwrap()
+        .update(task, percent, Some(step.to_string()));
+    match progress {
+        Ok(progress) => emit_progress(app, progress),
+        Err(e) => println!("Failed to report startup progress: {}", e),
+    }
+}
+
+fn finish_startup(app: &AppHandle) -> Result<(), String> {
+    let splash_window = app.get_webview_window("splashscreen");
+    let main_window = app.get_webview_window("main");
+    if let Some(splash) = splash_window {
+        splash.close().map_err(|e| format!("Failed to close splashscreen: {}", e))?;
+    } else {
+        return Err("Splashscreen window not found".to_string());
+    }
+    if let Some(main) = main_window {
+        main.show().map_err(|e| format!("Failed to show main window: {}", e))?;
+    } else {
+        return Err("Main window not found".to_string());
+    }
+    Ok(())
+}
+
+async fn watch_startup_timeout(app: AppHandle) {
+    loop {
+        sleep(Duration::from_secs(1)).await;
+        let state = app.state::<Mutex<StartupRegistry>>();
+        let (timed_out, finished) = {
+            let mut registry = state.lock().unwrap();
+            (registry.check_timeout(), registry.is_finished())
+        };
+        for progress in timed_out {
+            println!("Startup task '{}' timed out", progress.task);
+            emit_progress(&app, progress);
+        }
+        if finished {
+            break;
+        }
+    }
+}
+
+async fn setup(app: AppHandle) -> Result<(), ()> {
+    println!("Performing backend setup...");
+    let pipeline = init::run_pipeline(&app, |percent, step| {
+        report_step(&app, "backend", percent, step)
+    });
+    let result = match pipeline {
+        Ok(()) => {
+            println!("Backend setup task completed!");
+            set_complete(app.clone(), app.state::<Mutex<StartupRegistry>>(), "backend".to_string())
+                .await
+        }
+        Err(e) => Err(e),
+    };
+    result.map_err(|e| {
+        println!("Setup failed: {}", e);
+        let progress = app
+            .state::<Mutex<StartupRegistry>>()
+            .lock()
+            .unwrap()
+            .fail("backend", ERROR_TASK_FAILED, e);
+        if let Ok(progress) = progress {
+            emit_progress(&app, progress);
+        }
+    })
+}
@@ -0,0 +1,39 @@
+// Mirrors src/constants/game.ts, keep both in sync.
+
+// Canvas and Map dimensions
+pub const CANVAS_WIDTH: f64 = 960.0;
+pub const CANVAS_HEIGHT: f64 = 540.0;
+pub const MAP_WIDTH: f64 = 3000.0;
+pub const MAP_HEIGHT: f64 = 3000.0;
+
+// Player settings
+pub const PLAYER_SPEED: f64 = 200.0; // pixels per second
+
+// Predefined player colors, see PLAYER_COLORS in game.ts
+#[cfg(feature = "gui")]
+pub const PLAYER_COLORS: [&str; 8] = [
+    "#e74c3c", // Red
+    "#3498db", // Blue
+    "#2ecc71", // Green
+    "#f1c40f", // Yellow
+    "#9b59b6", // Purple
+    "#e67e22", // Orange
+    "#1abc9c", // Teal
+    "#e84393", // Pink
+];
+
+// Default player color (Blue)
+#[cfg(feature = "gui")]
+pub const DEFAULT_PLAYER_COLOR: &str = PLAYER_COLORS[1];
+
+// Map settings
+pub const BORDER_WIDTH: f64 = 50.0;
+
+// Resource settings
+pub const RESOURCE_SIZE: f64 = 64.0; // Size of resource nodes
+pub const RESOURCE_GATHER_DISTANCE: f64 = 100.0;
+
+// Resource limits
+pub const MAX_TOTAL_SHARDS: u32 = 3000; // Maximum total shards player can hold
+
+// Per-tier values and caps live in src/data/tiers.json, see tiers.rs
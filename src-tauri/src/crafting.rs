@@ -0,0 +1,294 @@
+use crate::inventory::{self, Inventory};
+#[cfg(feature = "gui")]
+use crate::tiers;
+#[cfg(feature = "gui")]
+use crate::world::World;
+use crate::world::{PlayerState, WorldError};
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use std::collections::HashSet;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+/// Recipes bundled with the frontend.
+#[cfg(feature = "gui")]
+pub const DEFAULT_RECIPES: &str = include_str!("../../src/data/recipes.json");
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecipeInput {
+    pub rarity: u8,
+    pub count: u32,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+pub enum RecipeOutput {
+    Resource { rarity: u8, count: u32 },
+    Item { item: String, count: u32 },
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Recipe {
+    pub id: String,
+    pub name: String,
+    pub inputs: Vec<RecipeInput>,
+    pub output: RecipeOutput,
+}
+
+#[cfg(feature = "gui")]
+#[derive(Clone, Serialize, Deserialize)]
+pub struct RecipeBook {
+    pub recipes: Vec<Recipe>,
+}
+
+#[cfg(feature = "gui")]
+impl RecipeBook {
+    pub fn get(&self, recipe_id: &str) -> Result<&Recipe, WorldError> {
+        self.recipes
+            .iter()
+            .find(|recipe| recipe.id == recipe_id)
+            .ok_or_else(|| WorldError::UnknownRecipe(recipe_id.to_string()))
+    }
+}
+
+#[cfg(feature = "gui")]
+fn check_rarity(recipe: &Recipe, rarity: u8) -> Result<(), String> {
+    if tiers::tier(rarity).is_none() {
+        return Err(format!(
+            "Recipe '{}' uses unknown rarity {}",
+            recipe.id, rarity
+        ));
+    }
+    Ok(())
+}
+
+/// Parses and validates a recipe file.
+#[cfg(feature = "gui")]
+pub fn parse_recipes(json: &str) -> Result<RecipeBook, String> {
+    let book: RecipeBook =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse recipes: {}", e))?;
+    let mut ids = HashSet::new();
+    for recipe in &book.recipes {
+        if recipe.id.is_empty() {
+            return Err("Recipe without an id".to_string());
+        }
+        if !ids.insert(recipe.id.as_str()) {
+            return Err(format!("Recipe '{}' is defined twice", recipe.id));
+        }
+        if recipe.inputs.is_empty() {
+            return Err(format!("Recipe '{}' has no inputs", recipe.id));
+        }
+        for input in &recipe.inputs {
+            check_rarity(recipe, input.rarity)?;
+            if input.count == 0 {
+                return Err(format!("Recipe '{}' has an empty input", recipe.id));
+            }
+        }
+        let count = match &recipe.output {
+            RecipeOutput::Resource { rarity, count } => {
+                check_rarity(recipe, *rarity)?;
+                *count
+            }
+            RecipeOutput::Item { item, count } => {
+                if item.is_empty() {
+                    return Err(format!("Recipe '{}' outputs an unnamed item", recipe.id));
+                }
+                *count
+            }
+        };
+        if count == 0 {
+            return Err(format!("Recipe '{}' has an empty output", recipe.id));
+        }
+    }
+    Ok(book)
+}
+
+/// The player's inventory after crafting `recipe` once, or why it cannot be
+/// crafted. Nothing is changed, so checking and crafting share one rule set.
+pub fn plan(player: &PlayerState, recipe: &Recipe) -> Result<Inventory, WorldError> {
+    let mut inventory = player.inventory.clone();
+    for input in &recipe.inputs {
+        let available = inventory.get(&input.rarity).copied().unwrap_or(0);
+        if available < input.count {
+            return Err(WorldError::NotEnough {
+                rarity: input.rarity,
+                requested: input.count,
+                available,
+            });
+        }
+        if available == input.count {
+            inventory.remove(&input.rarity);
+        } else {
+            inventory.insert(input.rarity, available - input.count);
+        }
+    }
+    if let RecipeOutput::Resource { rarity, count } = recipe.output {
+        if inventory::room_for(&inventory, rarity) < count {
+            return Err(WorldError::InventoryFull { rarity });
+        }
+        *inventory.entry(rarity).or_insert(0) += count;
+    }
+    Ok(inventory)
+}
+
+/// Crafts `recipe` once: either every input is consumed and the output
+/// added, or the player is left untouched.
+pub fn apply(player: &mut PlayerState, recipe: &Recipe) -> Result<(), WorldError> {
+    player.inventory = plan(player, recipe)?;
+    if let RecipeOutput::Item { item, count } = &recipe.output {
+        *player.items.entry(item.clone()).or_insert(0) += count;
+    }
+    Ok(())
+}
+
+#[cfg(feature = "gui")]
+#[derive(Serialize)]
+pub struct CraftCheck {
+    pub craftable: bool,
+    /// Why the recipe cannot be crafted right now.
+    pub reason: Option<WorldError>,
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn list_recipes(book: State<'_, RecipeBook>) -> Vec<Recipe> {
+    book.recipes.clone()
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn can_craft(
+    book: State<'_, RecipeBook>,
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    recipe_id: String,
+) -> Result<CraftCheck, WorldError> {
+    let recipe = book.get(&recipe_id)?;
+    let world = world.lock().unwrap();
+    let player = world
+        .player(&player_id)
+        .ok_or_else(|| WorldError::UnknownPlayer(player_id.clone()))?;
+    let reason = plan(player, recipe).err();
+    Ok(CraftCheck {
+        craftable: reason.is_none(),
+        reason,
+    })
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn craft(
+    book: State<'_, RecipeBook>,
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    recipe_id: String,
+) -> Result<PlayerState, WorldError> {
+    let recipe = book.get(&recipe_id)?;
+    world.lock().unwrap().craft(&player_id, recipe)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::tiers;
+    use crate::vector2::Vector2;
+    use crate::world::World;
+
+    fn player_with(counts: &[(u8, u32)]) -> PlayerState {
+        let mut world = World::new();
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        let mut player = world.player("a").unwrap().clone();
+        player.inventory = counts.iter().copied().collect();
+        player
+    }
+
+    fn recipe(inputs: &[(u8, u32)], output: RecipeOutput) -> Recipe {
+        Recipe {
+            id: "test".to_string(),
+            name: "Test".to_string(),
+            inputs: inputs
+                .iter()
+                .map(|&(rarity, count)| RecipeInput { rarity, count })
+                .collect(),
+            output,
+        }
+    }
+
+    #[cfg(feature = "gui")]
+    #[test]
+    fn bundled_recipes_parse() {
+        assert!(parse_recipes(DEFAULT_RECIPES).is_ok());
+    }
+
+    #[test]
+    fn crafting_consumes_inputs_and_adds_the_output() {
+        let mut player = player_with(&[(0, 7), (1, 1)]);
+        let refine = recipe(
+            &[(0, 5)],
+            RecipeOutput::Resource {
+                rarity: 1,
+                count: 1,
+            },
+        );
+        apply(&mut player, &refine).unwrap();
+        assert_eq!(
+            player.inventory,
+            [(0, 2), (1, 2)].into_iter().collect::<Inventory>()
+        );
+
+        let tool = recipe(
+            &[(0, 2), (1, 2)],
+            RecipeOutput::Item {
+                item: "pickaxe".to_string(),
+                count: 1,
+            },
+        );
+        apply(&mut player, &tool).unwrap();
+        assert!(player.inventory.is_empty());
+        assert_eq!(player.items.get("pickaxe"), Some(&1));
+    }
+
+    #[test]
+    fn a_missing_input_leaves_the_player_untouched() {
+        let mut player = player_with(&[(0, 5), (1, 1)]);
+        let before = player.inventory.clone();
+        let tool = recipe(
+            &[(0, 5), (1, 2)],
+            RecipeOutput::Item {
+                item: "pickaxe".to_string(),
+                count: 1,
+            },
+        );
+        assert!(matches!(
+            apply(&mut player, &tool),
+            Err(WorldError::NotEnough {
+                rarity: 1,
+                requested: 2,
+                available: 1
+            })
+        ));
+        assert_eq!(player.inventory, before);
+        assert!(player.items.is_empty());
+    }
+
+    #[test]
+    fn an_output_that_does_not_fit_leaves_the_player_untouched() {
+        let cap = tiers::tier(4).unwrap().carry_cap.unwrap();
+        let mut player = player_with(&[(3, 2), (4, cap)]);
+        let before = player.inventory.clone();
+        let refine = recipe(
+            &[(3, 2)],
+            RecipeOutput::Resource {
+                rarity: 4,
+                count: 1,
+            },
+        );
+        assert!(matches!(
+            apply(&mut player, &refine),
+            Err(WorldError::InventoryFull { rarity: 4 })
+        ));
+        assert_eq!(player.inventory, before);
+    }
+}
@@ -0,0 +1,238 @@
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::net::{Ipv4Addr, SocketAddr};
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Manager, State};
+use tokio::net::UdpSocket;
+use tokio::time::{interval, Duration};
+
+/// UDP port hosts announce to and clients listen on.
+pub const DISCOVERY_PORT: u16 = 7778;
+
+/// Marks our datagrams, so anything else sent to the port is ignored.
+const GAME_ID: &str = "the-gatherer";
+
+const ANNOUNCE_INTERVAL: Duration = Duration::from_secs(1);
+
+/// Sessions not heard from for this long are dropped from the list.
+const SESSION_TIMEOUT: Duration = Duration::from_secs(5);
+
+/// Largest announcement accepted, well above what a session sends.
+const MAX_DATAGRAM: usize = 2048;
+
+/// Default announce target, every host on the local network.
+pub fn broadcast_address() -> SocketAddr {
+    SocketAddr::from((Ipv4Addr::BROADCAST, DISCOVERY_PORT))
+}
+
+/// What a host sends every `ANNOUNCE_INTERVAL`.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Announcement {
+    pub game: String,
+    pub version: String,
+    pub name: String,
+    /// Port of the game server, on the address the announcement came from.
+    pub port: u16,
+    pub player_count: usize,
+    pub map: String,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LanSession {
+    pub name: String,
+    pub host: String,
+    pub port: u16,
+    pub player_count: usize,
+    pub map: String,
+    pub version: String,
+    /// Whether this build can join, i.e. both run the same version.
+    pub compatible: bool,
+}
+
+/// Sessions heard recently, keyed by game server address.
+#[derive(Default)]
+pub struct LanSessions {
+    sessions: BTreeMap<SocketAddr, (LanSession, Instant)>,
+}
+
+impl LanSessions {
+    pub fn record(&mut self, from: SocketAddr, announcement: Announcement) {
+        let address = SocketAddr::new(from.ip(), announcement.port);
+        let session = LanSession {
+            name: announcement.name,
+            host: from.ip().to_string(),
+            port: announcement.port,
+            player_count: announcement.player_count,
+            map: announcement.map,
+            compatible: announcement.version == env!("CARGO_PKG_VERSION"),
+            version: announcement.version,
+        };
+        self.sessions.insert(address, (session, Instant::now()));
+    }
+
+    /// Sessions heard within `SESSION_TIMEOUT`, forgetting older ones.
+    pub fn list(&mut self) -> Vec<LanSession> {
+        self.list_at(Instant::now())
+    }
+
+    fn list_at(&mut self, now: Instant) -> Vec<LanSession> {
+        self.sessions
+            .retain(|_, (_, heard)| now.duration_since(*heard) < SESSION_TIMEOUT);
+        self.sessions
+            .values()
+            .map(|(session, _)| session.clone())
+            .collect()
+    }
+}
+
+/// Announces the session hosted on `port` to `target` until the process
+/// stops. `target` is normally `broadcast_address()`; a loopback address
+/// works for sessions on the same machine without any network.
+pub async fn announce(
+    target: SocketAddr,
+    name: String,
+    map: String,
+    port: u16,
+    world: Arc<Mutex<World>>,
+) -> Result<(), String> {
+    let socket = UdpSocket::bind((Ipv4Addr::UNSPECIFIED, 0))
+        .await
+        .map_err(|e| format!("Failed to open announce socket: {}", e))?;
+    socket
+        .set_broadcast(true)
+        .map_err(|e| format!("Failed to enable broadcast: {}", e))?;
+
+    let mut ticker = interval(ANNOUNCE_INTERVAL);
+    let mut failing = false;
+    loop {
+        ticker.tick().await;
+        let announcement = Announcement {
+            game: GAME_ID.to_string(),
+            version: env!("CARGO_PKG_VERSION").to_string(),
+            name: name.clone(),
+            port,
+            player_count: world.lock().unwrap().players().count(),
+            map: map.clone(),
+        };
+        let datagram = serde_json::to_vec(&announcement).expect("announcements always serialize");
+        // Report a failing network once instead of every second.
+        match socket.send_to(&datagram, target).await {
+            Ok(_) => failing = false,
+            Err(e) if !failing => {
+                println!("Failed to announce session to {}: {}", target, e);
+                failing = true;
+            }
+            Err(_) => {}
+        }
+    }
+}
+
+/// Passes every valid announcement received on `socket` to `on_announcement`
+/// until the socket fails.
+pub async fn listen(
+    socket: UdpSocket,
+    mut on_announcement: impl FnMut(SocketAddr, Announcement),
+) -> Result<(), String> {
+    let mut buffer = [0u8; MAX_DATAGRAM];
+    loop {
+        let (length, from) = socket
+            .recv_from(&mut buffer)
+            .await
+            .map_err(|e| format!("Failed to receive announcement: {}", e))?;
+        let Ok(announcement) = serde_json::from_slice::<Announcement>(&buffer[..length]) else {
+            continue;
+        };
+        if announcement.game == GAME_ID {
+            on_announcement(from, announcement);
+        }
+    }
+}
+
+/// Collects sessions announced on `DISCOVERY_PORT` for `list_lan_sessions`.
+#[cfg(feature = "gui")]
+pub async fn run(app: AppHandle) {
+    let socket = match UdpSocket::bind((Ipv4Addr::UNSPECIFIED, DISCOVERY_PORT)).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!(
+                "LAN discovery unavailable on port {}: {}",
+                DISCOVERY_PORT, e
+            );
+            return;
+        }
+    };
+    let result = listen(socket, |from, announcement| {
+        app.state::<Mutex<LanSessions>>()
+            .lock()
+            .unwrap()
+            .record(from, announcement);
+    })
+    .await;
+    if let Err(e) = result {
+        println!("LAN discovery stopped: {}", e);
+    }
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn list_lan_sessions(sessions: State<'_, Mutex<LanSessions>>) -> Vec<LanSession> {
+    sessions.lock().unwrap().list()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use tokio::time::{sleep, timeout};
+
+    #[tokio::test]
+    async fn announced_sessions_are_listed_until_they_time_out() {
+        let socket = UdpSocket::bind((Ipv4Addr::LOCALHOST, 0)).await.unwrap();
+        let target = socket.local_addr().unwrap();
+        let mut world = World::new();
+        world.join("a", "A", crate::map::spawn_point());
+        let announcer = tokio::spawn(announce(
+            target,
+            "Test".to_string(),
+            "default".to_string(),
+            7777,
+            Arc::new(Mutex::new(world)),
+        ));
+        let sessions = Arc::new(Mutex::new(LanSessions::default()));
+        let listener = tokio::spawn(listen(socket, {
+            let sessions = sessions.clone();
+            move |from, announcement| sessions.lock().unwrap().record(from, announcement)
+        }));
+
+        let listed = timeout(Duration::from_secs(5), async {
+            loop {
+                let listed = sessions.lock().unwrap().list();
+                if !listed.is_empty() {
+                    return listed;
+                }
+                sleep(Duration::from_millis(10)).await;
+            }
+        })
+        .await
+        .expect("the announcement arrives");
+        announcer.abort();
+        listener.abort();
+
+        assert_eq!(listed.len(), 1);
+        let session = &listed[0];
+        assert_eq!(session.name, "Test");
+        assert_eq!(session.host, "127.0.0.1");
+        assert_eq!(session.port, 7777);
+        assert_eq!(session.player_count, 1);
+        assert_eq!(session.map, "default");
+        assert!(session.compatible);
+
+        let mut sessions = sessions.lock().unwrap();
+        assert_eq!(sessions.list_at(Instant::now() + SESSION_TIMEOUT).len(), 0);
+        assert!(sessions.list().is_empty());
+    }
+}
@@ -0,0 +1,236 @@
+use crate::constants::{BORDER_WIDTH, MAP_HEIGHT, MAP_WIDTH, MAX_TOTAL_SHARDS, RESOURCE_SIZE};
+#[cfg(feature = "gui")]
+use crate::map::{self, MapError, MapState};
+use crate::map::{MapData, ResourceLocation};
+use crate::tiers;
+use crate::vector2::Vector2;
+#[cfg(feature = "gui")]
+use crate::world::World;
+use serde::{Deserialize, Serialize};
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+/// Candidate positions tried per node before giving up on it.
+const MAX_PLACEMENT_ATTEMPTS: u32 = 200;
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase", default)]
+pub struct GeneratorParams {
+    pub map_width: f64,
+    pub map_height: f64,
+    pub border_width: f64,
+    /// Number of nodes to place per rarity, indexed by rarity.
+    pub tier_caps: Vec<usize>,
+    /// Minimum distance between any two nodes.
+    pub min_spacing: f64,
+    /// Total shard value the layout may not exceed.
+    pub max_total_shards: u32,
+}
+
+impl Default for GeneratorParams {
+    fn default() -> Self {
+        Self {
+            map_width: MAP_WIDTH,
+            map_height: MAP_HEIGHT,
+            border_width: BORDER_WIDTH,
+            tier_caps: tiers::tiers()
+                .iter()
+                .map(|tier| tier.spawn_count as usize)
+                .collect(),
+            min_spacing: RESOURCE_SIZE,
+            max_total_shards: MAX_TOTAL_SHARDS,
+        }
+    }
+}
+
+/// SplitMix64. Kept in-tree so a seed produces the same map on every
+/// platform and across dependency upgrades.
+pub struct SeededRng {
+    state: u64,
+}
+
+/// Seeded from the clock, for rolls that need no reproducibility.
+impl Default for SeededRng {
+    fn default() -> Self {
+        let seed = std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos() as u64)
+            .unwrap_or_default();
+        Self::new(seed)
+    }
+}
+
+impl SeededRng {
+    pub fn new(seed: u64) -> Self {
+        Self { state: seed }
+    }
+
+    pub fn next_u64(&mut self) -> u64 {
+        self.state = self.state.wrapping_add(0x9E37_79B9_7F4A_7C15);
+        let mut z = self.state;
+        z = (z ^ (z >> 30)).wrapping_mul(0xBF58_476D_1CE4_E5B9);
+        z = (z ^ (z >> 27)).wrapping_mul(0x94D0_49BB_1331_11EB);
+        z ^ (z >> 31)
+    }
+
+    /// Uniform value in `[min, max)`.
+    pub fn range(&mut self, min: f64, max: f64) -> f64 {
+        let unit = (self.next_u64() >> 11) as f64 / (1u64 << 53) as f64;
+        min + unit * (max - min)
+    }
+}
+
+/// Builds a layout from `seed`. Rarer tiers are placed first so they always
+/// get a spot; a tier stops early once the shard budget would be exceeded or
+/// no position respecting `min_spacing` can be found.
+pub fn generate(seed: u64, params: &GeneratorParams) -> Result<MapData, String> {
+    if params.border_width * 2.0 >= params.map_width.min(params.map_height) {
+        return Err("Border leaves no playable area".to_string());
+    }
+    if params.tier_caps.len() > tiers::tiers().len() {
+        return Err(format!(
+            "Expected at most {} tier caps, got {}",
+            tiers::tiers().len(),
+            params.tier_caps.len()
+        ));
+    }
+    if params.min_spacing < 0.0 {
+        return Err("Minimum spacing cannot be negative".to_string());
+    }
+
+    let mut rng = SeededRng::new(seed);
+    let mut placed: Vec<Vector2> = Vec::new();
+    let mut locations = Vec::new();
+    let mut total_shards = 0;
+
+    for (rarity, &cap) in params.tier_caps.iter().enumerate().rev() {
+        let shard_value = tiers::shard_value(rarity as u8);
+        for _ in 0..cap {
+            if total_shards + shard_value > params.max_total_shards {
+                break;
+            }
+            let Some(position) = find_position(&mut rng, params, &placed) else {
+                break;
+            };
+            placed.push(position);
+            locations.push(ResourceLocation {
+                x: position.x,
+                y: position.y,
+                rarity: rarity as u8,
+            });
+            total_shards += shard_value;
+        }
+    }
+
+    Ok(MapData {
+        locations,
+        base: None,
+        obstacles: Vec::new(),
+    })
+}
+
+fn find_position(
+    rng: &mut SeededRng,
+    params: &GeneratorParams,
+    placed: &[Vector2],
+) -> Option<Vector2> {
+    let max_x = params.map_width - params.border_width;
+    let max_y = params.map_height - params.border_width;
+    for _ in 0..MAX_PLACEMENT_ATTEMPTS {
+        // Whole pixels keep resource ids stable, see `ResourceNode::new`.
+        let candidate = Vector2::new(
+            rng.range(params.border_width, max_x).round(),
+            rng.range(params.border_width, max_y).round(),
+        );
+        if placed
+            .iter()
+            .all(|other| other.distance_to(candidate) >= params.min_spacing)
+        {
+            return Some(candidate);
+        }
+    }
+    None
+}
+
+/// Generates a layout from `seed`. With `apply` the world is rebuilt from it,
+/// as long as it passes the same validation as a loaded map, otherwise the
+/// layout is only returned, e.g. for balance testing.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn generate_map(
+    maps: State<'_, Mutex<MapState>>,
+    world: State<'_, Mutex<World>>,
+    seed: u64,
+    params: Option<GeneratorParams>,
+    apply: Option<bool>,
+) -> Result<MapData, String> {
+    let map = generate(seed, &params.unwrap_or_default())?;
+    if apply.unwrap_or(false) {
+        // Custom params can go beyond what the game accepts, e.g. more nodes
+        // of a tier than its cap.
+        let issues = map::validate_map(&map);
+        if !issues.is_empty() {
+            return Err(MapError::Invalid(issues).to_string());
+        }
+        world.lock().unwrap().load_map(&map);
+        maps.lock().unwrap().current = Some(map.clone());
+    }
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map;
+
+    #[test]
+    fn a_seed_always_gives_the_same_layout() {
+        let params = GeneratorParams::default();
+        let first = serde_json::to_string(&generate(7, &params).unwrap()).unwrap();
+        let second = serde_json::to_string(&generate(7, &params).unwrap()).unwrap();
+        assert_eq!(first, second);
+
+        let other = serde_json::to_string(&generate(8, &params).unwrap()).unwrap();
+        assert_ne!(first, other);
+    }
+
+    #[test]
+    fn default_layouts_are_valid_maps() {
+        for seed in 0..20 {
+            let map = generate(seed, &GeneratorParams::default()).unwrap();
+            assert!(map::validate_map(&map).is_empty(), "seed {}", seed);
+        }
+    }
+
+    #[test]
+    fn layouts_respect_spacing_and_the_shard_budget() {
+        let params = GeneratorParams {
+            max_total_shards: 600,
+            ..GeneratorParams::default()
+        };
+        let map = generate(3, &params).unwrap();
+        let total: u32 = map
+            .locations
+            .iter()
+            .map(|location| tiers::shard_value(location.rarity))
+            .sum();
+        assert!(total <= 600);
+        for (index, a) in map.locations.iter().enumerate() {
+            for b in &map.locations[index + 1..] {
+                let distance = Vector2::new(a.x, a.y).distance_to(Vector2::new(b.x, b.y));
+                assert!(distance >= params.min_spacing);
+            }
+        }
+    }
+
+    #[test]
+    fn invalid_params_are_rejected() {
+        let params = GeneratorParams {
+            border_width: 2000.0,
+            ..GeneratorParams::default()
+        };
+        assert!(generate(1, &params).is_err());
+    }
+}
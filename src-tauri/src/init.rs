@@ -0,0 +1,151 @@
+use crate::crafting::{self, RecipeBook};
+use crate::map::{self, MapState};
+use crate::profile::ProfileStore;
+use crate::save::SaveStore;
+use crate::tiers;
+use crate::tools;
+use crate::world::World;
+use std::path::Path;
+use std::sync::Mutex;
+use std::time::Instant;
+use tauri::{AppHandle, Manager};
+
+/// Assets the frontend loads through `ASSET_PATHS`, besides the tier
+/// textures from the tier table.
+const BUNDLED_ASSETS: [&str; 2] = ["/assets/grass.png", "/assets/cliff.png"];
+
+/// Runs every backend initialization step in order. `report` receives the
+/// percentage and label of each step as it starts.
+pub fn run_pipeline(app: &AppHandle, report: impl Fn(u8, &str)) -> Result<(), String> {
+    let started = Instant::now();
+
+    report(0, "Loading map data");
+    let map = timed("Loading map data", || {
+        // Maps are validated against the tier table.
+        tiers::load()?;
+        map::parse_map(map::DEFAULT_MAP).map_err(|e| e.to_string())
+    })?;
+
+    report(10, "Loading recipes and tools");
+    timed("Loading recipes and tools", || load_recipes(app))?;
+
+    report(20, "Loading player profile");
+    let data_dir = app
+        .path()
+        .app_data_dir()
+        .map_err(|e| format!("Failed to resolve app data directory: {}", e))?;
+    timed("Loading player profile", || load_profile(app, &data_dir))?;
+
+    report(40, "Checking game assets");
+    timed("Checking game assets", || check_assets(app))?;
+
+    report(60, "Warming caches");
+    timed("Warming caches", || {
+        let mut counts = vec![0usize; tiers::tiers().len()];
+        for location in &map.locations {
+            if let Some(count) = counts.get_mut(location.rarity as usize) {
+                *count += 1;
+            }
+        }
+        println!("Map tiers: {:?}", counts);
+        app.state::<Mutex<World>>().lock().unwrap().load_map(&map);
+        app.state::<Mutex<MapState>>().lock().unwrap().current = Some(map.clone());
+        Ok(())
+    })?;
+
+    report(80, "Loading saved game");
+    timed("Loading saved game", || load_save(app, &data_dir))?;
+
+    println!(
+        "Backend initialization took {}ms",
+        started.elapsed().as_millis()
+    );
+    Ok(())
+}
+
+fn timed<T>(label: &str, step: impl FnOnce() -> Result<T, String>) -> Result<T, String> {
+    let started = Instant::now();
+    let result = step().map_err(|e| format!("{}: {}", label, e));
+    println!("{} took {}ms", label, started.elapsed().as_millis());
+    result
+}
+
+fn load_profile(app: &AppHandle, data_dir: &Path) -> Result<(), String> {
+    let store = ProfileStore::open(data_dir).map_err(|e| e.to_string())?;
+    println!("Loaded profile for {}", store.profile().player_id);
+    // A retried startup replaces the store managed by the previous attempt.
+    match app.try_state::<Mutex<ProfileStore>>() {
+        Some(existing) => *existing.lock().unwrap() = store,
+        None => {
+            app.manage(Mutex::new(store));
+        }
+    }
+    Ok(())
+}
+
+fn load_recipes(app: &AppHandle) -> Result<(), String> {
+    let book = crafting::parse_recipes(crafting::DEFAULT_RECIPES)?;
+    let tools = tools::parse_tools(tools::DEFAULT_TOOLS)?;
+    println!(
+        "Loaded {} recipes and {} tools",
+        book.recipes.len(),
+        tools.len()
+    );
+    app.state::<Mutex<World>>().lock().unwrap().set_tools(tools);
+    // Recipes are bundled, so a retried startup has nothing new to load.
+    if app.try_state::<RecipeBook>().is_none() {
+        app.manage(book);
+    }
+    Ok(())
+}
+
+/// Joins the local player at the map center and restores its progress from
+/// the last save, if there is one.
+fn load_save(app: &AppHandle, data_dir: &Path) -> Result<(), String> {
+    let store = SaveStore::new(data_dir);
+    let save = store.read()?;
+    let (player_id, display_name) = {
+        let profiles = app.state::<Mutex<ProfileStore>>();
+        let profiles = profiles.lock().unwrap();
+        let profile = profiles.profile();
+        (profile.player_id.clone(), profile.display_name.clone())
+    };
+    {
+        let world = app.state::<Mutex<World>>();
+        let mut world = world.lock().unwrap();
+        world.join(&player_id, &display_name, map::spawn_point());
+        match save {
+            Some(save) if save.player_id == player_id => {
+                println!("Restoring save from {}", save.saved_at);
+                world
+                    .restore_progress(&player_id, save.progress())
+                    .map_err(|e| e.to_string())?;
+            }
+            Some(save) => println!("Ignoring save of other player {}", save.player_id),
+            None => println!("No save found, starting fresh"),
+        }
+    }
+    if app.try_state::<SaveStore>().is_none() {
+        app.manage(store);
+    }
+    Ok(())
+}
+
+fn check_assets(app: &AppHandle) -> Result<(), String> {
+    // In development the frontend is served by Vite and nothing is embedded.
+    if tauri::is_dev() {
+        return Ok(());
+    }
+    let resolver = app.asset_resolver();
+    let textures = tiers::tiers().iter().map(|tier| tier.texture.as_str());
+    let missing: Vec<&str> = BUNDLED_ASSETS
+        .iter()
+        .copied()
+        .chain(textures)
+        .filter(|path| resolver.get(path.to_string()).is_none())
+        .collect();
+    if !missing.is_empty() {
+        return Err(format!("Missing assets: {}", missing.join(", ")));
+    }
+    Ok(())
+}
@@ -0,0 +1,209 @@
+use crate::constants::{CANVAS_HEIGHT, CANVAS_WIDTH, MAP_HEIGHT, MAP_WIDTH};
+use crate::tick::TICK_RATE;
+use crate::vector2::Vector2;
+use crate::world::{World, WorldSnapshot};
+use serde::Serialize;
+
+/// Distance around the viewport that is still streamed, so entities arrive
+/// shortly before they scroll into view.
+pub const INTEREST_MARGIN: f64 = 256.0;
+
+/// Ticks between overviews of the whole map, once per second.
+pub const OVERVIEW_INTERVAL: u64 = TICK_RATE as u64;
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerMarker {
+    pub id: String,
+    pub display_name: String,
+    pub position: Vector2,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceMarker {
+    pub id: String,
+    pub rarity: u8,
+    pub position: Vector2,
+    pub available: bool,
+}
+
+/// Every entity on the map reduced to what the minimap draws.
+#[derive(Clone, Serialize)]
+pub struct Overview {
+    pub tick: u64,
+    pub players: Vec<PlayerMarker>,
+    pub resources: Vec<ResourceMarker>,
+}
+
+pub fn overview(world: &World, tick: u64) -> Overview {
+    Overview {
+        tick,
+        players: world
+            .players()
+            .map(|player| PlayerMarker {
+                id: player.id.clone(),
+                display_name: player.display_name.clone(),
+                position: player.position,
+            })
+            .collect(),
+        resources: world
+            .resources()
+            .map(|node| ResourceMarker {
+                id: node.id.clone(),
+                rarity: node.rarity,
+                position: node.position,
+                available: node.is_available(),
+            })
+            .collect(),
+    }
+}
+
+/// The overview due at `tick`, one every `OVERVIEW_INTERVAL` ticks.
+pub fn scheduled_overview(world: &World, tick: u64) -> Option<Overview> {
+    tick.is_multiple_of(OVERVIEW_INTERVAL)
+        .then(|| overview(world, tick))
+}
+
+/// One client's camera. The entities the client knows about are tracked by
+/// its `SyncEncoder`.
+pub struct Interest {
+    /// Center of the view. Follows the client's player until set.
+    camera: Option<Vector2>,
+    viewport: Vector2,
+}
+
+impl Default for Interest {
+    fn default() -> Self {
+        Self {
+            camera: None,
+            viewport: Vector2::new(CANVAS_WIDTH, CANVAS_HEIGHT),
+        }
+    }
+}
+
+impl Interest {
+    pub fn set_camera(&mut self, center: Vector2, viewport: Option<Vector2>) {
+        self.camera = Some(center);
+        if let Some(viewport) = viewport {
+            self.viewport = Vector2::new(viewport.x.max(0.0), viewport.y.max(0.0));
+        }
+    }
+
+    /// Viewport plus margin around the camera.
+    fn area(&self, world: &World, player_id: &str) -> (Vector2, Vector2) {
+        let center = self
+            .camera
+            .or_else(|| world.player(player_id).map(|player| player.position))
+            .unwrap_or_else(|| Vector2::new(MAP_WIDTH / 2.0, MAP_HEIGHT / 2.0));
+        let half = Vector2::new(
+            self.viewport.x / 2.0 + INTEREST_MARGIN,
+            self.viewport.y / 2.0 + INTEREST_MARGIN,
+        );
+        (center - half, center + half)
+    }
+
+    /// The part of the world inside the area. The client's own player is
+    /// always included so its inventory stays current wherever the camera is.
+    pub fn snapshot(&self, world: &World, player_id: &str) -> WorldSnapshot {
+        let (min, max) = self.area(world, player_id);
+        let mut nearby = world.query_rect(min, max);
+        if !nearby.players.iter().any(|player| player.id == player_id) {
+            nearby.players.extend(world.player(player_id).cloned());
+        }
+        WorldSnapshot {
+            base: world.base(),
+            resources: nearby.resources,
+            players: nearby.players,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::{MapData, ResourceLocation};
+    use crate::sync::SyncEncoder;
+
+    const NEAR: &str = "resource_500_500_0";
+    const FAR: &str = "resource_2500_2500_0";
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.load_map(&MapData {
+            locations: vec![
+                ResourceLocation {
+                    x: 500.0,
+                    y: 500.0,
+                    rarity: 0,
+                },
+                ResourceLocation {
+                    x: 2500.0,
+                    y: 2500.0,
+                    rarity: 0,
+                },
+            ],
+            base: None,
+            obstacles: Vec::new(),
+        });
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        world.join("b", "B", Vector2::new(2500.0, 2500.0));
+        world
+    }
+
+    fn ids<'a>(entities: impl IntoIterator<Item = &'a String>) -> Vec<&'a str> {
+        entities.into_iter().map(String::as_str).collect()
+    }
+
+    #[test]
+    fn snapshots_only_hold_the_area_around_the_camera() {
+        let world = world();
+        let mut interest = Interest::default();
+        let snapshot = interest.snapshot(&world, "a");
+        assert_eq!(ids(snapshot.resources.iter().map(|node| &node.id)), [NEAR]);
+        assert_eq!(ids(snapshot.players.iter().map(|player| &player.id)), ["a"]);
+
+        // Just outside the viewport plus margin.
+        let edge = CANVAS_WIDTH / 2.0 + INTEREST_MARGIN;
+        interest.set_camera(Vector2::new(500.0 - edge - 1.0, 500.0), None);
+        let snapshot = interest.snapshot(&world, "a");
+        assert!(snapshot.resources.is_empty());
+        // The client's own player is kept anyway.
+        assert_eq!(ids(snapshot.players.iter().map(|player| &player.id)), ["a"]);
+    }
+
+    #[test]
+    fn moving_the_camera_adds_and_removes_entities() {
+        let world = world();
+        let mut interest = Interest::default();
+        let mut encoder = SyncEncoder::default();
+        let first = encoder
+            .encode_reliable(0, &interest.snapshot(&world, "a"), &[])
+            .unwrap();
+        assert_eq!(ids(first.resources.iter().map(|patch| &patch.id)), [NEAR]);
+
+        interest.set_camera(Vector2::new(2500.0, 2500.0), None);
+        let moved = encoder
+            .encode_reliable(1, &interest.snapshot(&world, "a"), &[])
+            .unwrap();
+        assert_eq!(ids(moved.resources.iter().map(|patch| &patch.id)), [FAR]);
+        assert_eq!(ids(&moved.removed_resources), [NEAR]);
+        assert_eq!(ids(moved.players.iter().map(|patch| &patch.id)), ["b"]);
+        assert!(moved.removed_players.is_empty());
+    }
+
+    #[test]
+    fn overviews_follow_their_interval() {
+        let world = world();
+        let overview = scheduled_overview(&world, 0).unwrap();
+        assert_eq!(overview.players.len(), 2);
+        assert_eq!(overview.resources.len(), 2);
+        for tick in 1..OVERVIEW_INTERVAL {
+            assert!(scheduled_overview(&world, tick).is_none());
+        }
+        assert_eq!(
+            scheduled_overview(&world, OVERVIEW_INTERVAL).map(|overview| overview.tick),
+            Some(OVERVIEW_INTERVAL)
+        );
+    }
+}
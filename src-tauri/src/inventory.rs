@@ -0,0 +1,95 @@
+use crate::constants::MAX_TOTAL_SHARDS;
+use crate::tiers::{self, shard_value};
+use serde::Serialize;
+use std::collections::BTreeMap;
+
+/// Resource counts keyed by rarity.
+pub type Inventory = BTreeMap<u8, u32>;
+
+/// How many resources of `rarity` a player may carry. Tiers without a carry
+/// cap are only bounded by `MAX_TOTAL_SHARDS`, unknown rarities cannot be
+/// carried.
+pub fn carry_limit(rarity: u8) -> Option<u32> {
+    match tiers::tier(rarity) {
+        Some(tier) => tier.carry_cap,
+        None => Some(0),
+    }
+}
+
+/// Saturates instead of overflowing, so a corrupt save holding huge counts
+/// reads as a full inventory.
+pub fn total_shards(inventory: &Inventory) -> u32 {
+    inventory.iter().fold(0, |total, (rarity, count)| {
+        total.saturating_add(shard_value(*rarity).saturating_mul(*count))
+    })
+}
+
+/// Number of additional resources of `rarity` that fit, limited by both the
+/// tier's carry limit and the remaining shard budget.
+pub fn room_for(inventory: &Inventory, rarity: u8) -> u32 {
+    let held = inventory.get(&rarity).copied().unwrap_or(0);
+    let by_shards = MAX_TOTAL_SHARDS.saturating_sub(total_shards(inventory)) / shard_value(rarity);
+    match carry_limit(rarity) {
+        Some(limit) => by_shards.min(limit.saturating_sub(held)),
+        None => by_shards,
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TierCapacity {
+    pub rarity: u8,
+    pub held: u32,
+    /// `None` when the tier has no carry limit of its own.
+    pub limit: Option<u32>,
+    pub remaining: u32,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Capacity {
+    pub max_total_shards: u32,
+    pub total_shards: u32,
+    pub remaining_shards: u32,
+    pub tiers: Vec<TierCapacity>,
+}
+
+pub fn capacity(inventory: &Inventory) -> Capacity {
+    let total_shards = total_shards(inventory);
+    Capacity {
+        max_total_shards: MAX_TOTAL_SHARDS,
+        total_shards,
+        remaining_shards: MAX_TOTAL_SHARDS.saturating_sub(total_shards),
+        tiers: (0..tiers::tier_count())
+            .map(|rarity| TierCapacity {
+                rarity,
+                held: inventory.get(&rarity).copied().unwrap_or(0),
+                limit: carry_limit(rarity),
+                remaining: room_for(inventory, rarity),
+            })
+            .collect(),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn huge_counts_saturate_instead_of_overflowing() {
+        let inventory: Inventory = [(0, u32::MAX), (5, u32::MAX)].into_iter().collect();
+        assert_eq!(total_shards(&inventory), u32::MAX);
+        for rarity in 0..tiers::tier_count() {
+            assert_eq!(room_for(&inventory, rarity), 0);
+        }
+        assert_eq!(capacity(&inventory).remaining_shards, 0);
+    }
+
+    #[test]
+    fn carry_limits_come_from_the_carry_cap() {
+        for tier in tiers::tiers() {
+            assert_eq!(carry_limit(tier.id), tier.carry_cap);
+        }
+        assert_eq!(carry_limit(tiers::tier_count()), Some(0));
+    }
+}
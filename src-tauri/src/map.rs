@@ -0,0 +1,586 @@
+use crate::constants::{BORDER_WIDTH, MAP_HEIGHT, MAP_WIDTH};
+use crate::tiers;
+use crate::vector2::Vector2;
+#[cfg(feature = "gui")]
+use crate::world::World;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::HashMap;
+use std::fmt;
+use std::fs;
+use std::path::Path;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+/// Layout bundled with the frontend, used when no other map is available.
+pub const DEFAULT_MAP: &str = include_str!("../../src/data/resources.json");
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct ResourceLocation {
+    pub x: f64,
+    pub y: f64,
+    pub rarity: u8,
+}
+
+/// Impassable rectangle such as a rock wall or a river, `x` and `y` being
+/// its top-left corner.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct Obstacle {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+}
+
+impl Obstacle {
+    pub fn contains(&self, point: Vector2) -> bool {
+        point.x > self.x
+            && point.x < self.x + self.width
+            && point.y > self.y
+            && point.y < self.y + self.height
+    }
+
+    /// Whether the obstacle overlaps the box from `min` to `max`.
+    pub fn overlaps(&self, min: Vector2, max: Vector2) -> bool {
+        self.x < max.x
+            && self.x + self.width > min.x
+            && self.y < max.y
+            && self.y + self.height > min.y
+    }
+
+    /// Liang-Barsky clip of the segment against the rectangle.
+    pub fn intersects_segment(&self, from: Vector2, to: Vector2) -> bool {
+        let delta = to - from;
+        let mut enter: f64 = 0.0;
+        let mut exit: f64 = 1.0;
+        let edges = [
+            (-delta.x, from.x - self.x),
+            (delta.x, self.x + self.width - from.x),
+            (-delta.y, from.y - self.y),
+            (delta.y, self.y + self.height - from.y),
+        ];
+        for (direction, distance) in edges {
+            if direction == 0.0 {
+                if distance <= 0.0 {
+                    return false;
+                }
+                continue;
+            }
+            let t = distance / direction;
+            if direction < 0.0 {
+                enter = enter.max(t);
+            } else {
+                exit = exit.min(t);
+            }
+            if enter >= exit {
+                return false;
+            }
+        }
+        true
+    }
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+pub struct MapData {
+    pub locations: Vec<ResourceLocation>,
+    /// Where the shard bank stands. Maps without one get it at the center.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<Vector2>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub obstacles: Vec<Obstacle>,
+}
+
+/// Where players join the world, the map center.
+pub fn spawn_point() -> Vector2 {
+    Vector2::new(MAP_WIDTH / 2.0, MAP_HEIGHT / 2.0)
+}
+
+/// The map the world is currently built from.
+#[cfg(feature = "gui")]
+#[derive(Default)]
+pub struct MapState {
+    pub current: Option<MapData>,
+}
+
+/// A single problem found while validating a map. `index` points into
+/// `locations` and `obstacle` into `obstacles`.
+#[derive(Debug)]
+pub enum MapIssue {
+    Empty,
+    OutOfBounds {
+        index: usize,
+        x: f64,
+        y: f64,
+    },
+    UnknownRarity {
+        index: usize,
+        rarity: u8,
+    },
+    Duplicate {
+        index: usize,
+        first: usize,
+    },
+    TooManyOfTier {
+        rarity: u8,
+        count: usize,
+        max: usize,
+    },
+    BaseOutOfBounds {
+        x: f64,
+        y: f64,
+    },
+    InvalidObstacle {
+        obstacle: usize,
+    },
+    Blocked {
+        index: usize,
+        obstacle: usize,
+    },
+    BaseBlocked {
+        obstacle: usize,
+    },
+    /// Players joining at `spawn_point` would start inside an obstacle.
+    SpawnBlocked {
+        obstacle: usize,
+    },
+}
+
+impl MapIssue {
+    pub fn code(&self) -> &'static str {
+        match self {
+            MapIssue::Empty => "empty",
+            MapIssue::OutOfBounds { .. } => "out_of_bounds",
+            MapIssue::UnknownRarity { .. } => "unknown_rarity",
+            MapIssue::Duplicate { .. } => "duplicate",
+            MapIssue::TooManyOfTier { .. } => "too_many_of_tier",
+            MapIssue::BaseOutOfBounds { .. } => "base_out_of_bounds",
+            MapIssue::InvalidObstacle { .. } => "invalid_obstacle",
+            MapIssue::Blocked { .. } => "blocked",
+            MapIssue::BaseBlocked { .. } => "base_blocked",
+            MapIssue::SpawnBlocked { .. } => "spawn_blocked",
+        }
+    }
+
+    pub fn index(&self) -> Option<usize> {
+        match self {
+            MapIssue::OutOfBounds { index, .. }
+            | MapIssue::UnknownRarity { index, .. }
+            | MapIssue::Duplicate { index, .. }
+            | MapIssue::Blocked { index, .. } => Some(*index),
+            MapIssue::Empty
+            | MapIssue::TooManyOfTier { .. }
+            | MapIssue::BaseOutOfBounds { .. }
+            | MapIssue::InvalidObstacle { .. }
+            | MapIssue::BaseBlocked { .. }
+            | MapIssue::SpawnBlocked { .. } => None,
+        }
+    }
+
+    pub fn obstacle(&self) -> Option<usize> {
+        match self {
+            MapIssue::InvalidObstacle { obstacle }
+            | MapIssue::Blocked { obstacle, .. }
+            | MapIssue::BaseBlocked { obstacle }
+            | MapIssue::SpawnBlocked { obstacle } => Some(*obstacle),
+            MapIssue::Empty
+            | MapIssue::OutOfBounds { .. }
+            | MapIssue::UnknownRarity { .. }
+            | MapIssue::Duplicate { .. }
+            | MapIssue::TooManyOfTier { .. }
+            | MapIssue::BaseOutOfBounds { .. } => None,
+        }
+    }
+}
+
+impl fmt::Display for MapIssue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapIssue::Empty => write!(f, "Map has no resource locations"),
+            MapIssue::OutOfBounds { index, x, y } => write!(
+                f,
+                "Location {} at ({}, {}) is outside the playable area",
+                index, x, y
+            ),
+            MapIssue::UnknownRarity { index, rarity } => {
+                write!(f, "Location {} has unknown rarity {}", index, rarity)
+            }
+            MapIssue::Duplicate { index, first } => {
+                write!(f, "Location {} duplicates location {}", index, first)
+            }
+            MapIssue::TooManyOfTier { rarity, count, max } => write!(
+                f,
+                "Map has {} resources of rarity {}, at most {} are allowed",
+                count, rarity, max
+            ),
+            MapIssue::BaseOutOfBounds { x, y } => {
+                write!(f, "Base at ({}, {}) is outside the playable area", x, y)
+            }
+            MapIssue::InvalidObstacle { obstacle } => write!(
+                f,
+                "Obstacle {} must have a positive size and lie inside the map",
+                obstacle
+            ),
+            MapIssue::Blocked { index, obstacle } => {
+                write!(f, "Location {} is inside obstacle {}", index, obstacle)
+            }
+            MapIssue::BaseBlocked { obstacle } => {
+                write!(f, "Base is inside obstacle {}", obstacle)
+            }
+            MapIssue::SpawnBlocked { obstacle } => write!(
+                f,
+                "Spawn point at the map center is inside obstacle {}",
+                obstacle
+            ),
+        }
+    }
+}
+
+impl Serialize for MapIssue {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("MapIssue", 4)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("index", &self.index())?;
+        state.serialize_field("obstacle", &self.obstacle())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+#[derive(Debug)]
+pub enum MapError {
+    Io(String),
+    Parse(String),
+    Invalid(Vec<MapIssue>),
+}
+
+impl MapError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            MapError::Io(_) => "io",
+            MapError::Parse(_) => "parse",
+            MapError::Invalid(_) => "invalid",
+        }
+    }
+}
+
+impl fmt::Display for MapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MapError::Io(e) => write!(f, "Failed to read map: {}", e),
+            MapError::Parse(e) => write!(f, "Failed to parse map data: {}", e),
+            MapError::Invalid(issues) => {
+                let messages: Vec<String> = issues.iter().map(|issue| issue.to_string()).collect();
+                write!(f, "Invalid map: {}", messages.join("; "))
+            }
+        }
+    }
+}
+
+impl std::error::Error for MapError {}
+
+impl Serialize for MapError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let issues: &[MapIssue] = match self {
+            MapError::Invalid(issues) => issues,
+            _ => &[],
+        };
+        let mut state = serializer.serialize_struct("MapError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("issues", issues)?;
+        state.end()
+    }
+}
+
+/// Checks a layout against the map bounds and tier caps, reporting every
+/// problem rather than stopping at the first.
+pub fn validate_map(map: &MapData) -> Vec<MapIssue> {
+    let mut issues = Vec::new();
+    if map.locations.is_empty() {
+        issues.push(MapIssue::Empty);
+    }
+
+    let min = BORDER_WIDTH;
+    let max_x = MAP_WIDTH - BORDER_WIDTH;
+    let max_y = MAP_HEIGHT - BORDER_WIDTH;
+    let in_bounds = |x: f64, y: f64| x >= min && x <= max_x && y >= min && y <= max_y;
+    let mut seen: HashMap<(u64, u64), usize> = HashMap::new();
+    let mut counts = vec![0usize; tiers::tiers().len()];
+
+    if let Some(base) = map.base {
+        if !in_bounds(base.x, base.y) {
+            issues.push(MapIssue::BaseOutOfBounds {
+                x: base.x,
+                y: base.y,
+            });
+        }
+    }
+
+    for (index, obstacle) in map.obstacles.iter().enumerate() {
+        let valid = obstacle.width > 0.0
+            && obstacle.height > 0.0
+            && obstacle.x >= 0.0
+            && obstacle.y >= 0.0
+            && obstacle.x + obstacle.width <= MAP_WIDTH
+            && obstacle.y + obstacle.height <= MAP_HEIGHT;
+        if !valid {
+            issues.push(MapIssue::InvalidObstacle { obstacle: index });
+        }
+    }
+
+    let blocking = |point: Vector2| {
+        map.obstacles
+            .iter()
+            .position(|obstacle| obstacle.contains(point))
+    };
+    // Maps without a base get it at the spawn point.
+    if let Some(obstacle) = map.base.and_then(blocking) {
+        issues.push(MapIssue::BaseBlocked { obstacle });
+    }
+    if let Some(obstacle) = blocking(spawn_point()) {
+        issues.push(MapIssue::SpawnBlocked { obstacle });
+    }
+
+    for (index, location) in map.locations.iter().enumerate() {
+        if let Some(obstacle) = blocking(Vector2::new(location.x, location.y)) {
+            issues.push(MapIssue::Blocked { index, obstacle });
+        }
+        if !in_bounds(location.x, location.y) {
+            issues.push(MapIssue::OutOfBounds {
+                index,
+                x: location.x,
+                y: location.y,
+            });
+        }
+
+        match counts.get_mut(location.rarity as usize) {
+            Some(count) => *count += 1,
+            None => issues.push(MapIssue::UnknownRarity {
+                index,
+                rarity: location.rarity,
+            }),
+        }
+
+        let key = (location.x.to_bits(), location.y.to_bits());
+        if let Some(&first) = seen.get(&key) {
+            issues.push(MapIssue::Duplicate { index, first });
+        } else {
+            seen.insert(key, index);
+        }
+    }
+
+    for (rarity, &count) in counts.iter().enumerate() {
+        let rarity = rarity as u8;
+        if let Some(max) = tiers::tier(rarity).and_then(|tier| tier.cap) {
+            let max = max as usize;
+            if count > max {
+                issues.push(MapIssue::TooManyOfTier { rarity, count, max });
+            }
+        }
+    }
+
+    issues
+}
+
+pub fn parse_map(json: &str) -> Result<MapData, MapError> {
+    let map: MapData = serde_json::from_str(json).map_err(|e| MapError::Parse(e.to_string()))?;
+    let issues = validate_map(&map);
+    if !issues.is_empty() {
+        return Err(MapError::Invalid(issues));
+    }
+    Ok(map)
+}
+
+pub fn load_map_file(path: &Path) -> Result<MapData, MapError> {
+    let contents =
+        fs::read_to_string(path).map_err(|e| MapError::Io(format!("{}: {}", path.display(), e)))?;
+    parse_map(&contents)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn get_map(maps: State<'_, Mutex<MapState>>) -> Result<MapData, String> {
+    maps.lock()
+        .unwrap()
+        .current
+        .clone()
+        .ok_or_else(|| "Map has not been loaded yet".to_string())
+}
+
+/// Loads and validates a map file, then rebuilds the world from it. Without a
+/// path the bundled default map is used.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn load_map(
+    maps: State<'_, Mutex<MapState>>,
+    world: State<'_, Mutex<World>>,
+    path: Option<String>,
+) -> Result<MapData, MapError> {
+    let map = match path {
+        Some(path) => load_map_file(Path::new(&path))?,
+        None => parse_map(DEFAULT_MAP)?,
+    };
+    world.lock().unwrap().load_map(&map);
+    maps.lock().unwrap().current = Some(map.clone());
+    Ok(map)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn map_with(base: Option<Vector2>, obstacles: Vec<Obstacle>) -> MapData {
+        MapData {
+            locations: vec![ResourceLocation {
+                x: 500.0,
+                y: 500.0,
+                rarity: 0,
+            }],
+            base,
+            obstacles,
+        }
+    }
+
+    fn around(point: Vector2) -> Obstacle {
+        Obstacle {
+            x: point.x - 20.0,
+            y: point.y - 20.0,
+            width: 40.0,
+            height: 40.0,
+        }
+    }
+
+    fn codes(map: &MapData) -> Vec<&'static str> {
+        validate_map(map).iter().map(MapIssue::code).collect()
+    }
+
+    #[test]
+    fn bundled_map_is_valid() {
+        assert!(parse_map(DEFAULT_MAP).is_ok());
+    }
+
+    #[test]
+    fn obstacles_may_not_cover_the_spawn_point() {
+        let map = map_with(None, vec![around(spawn_point())]);
+        assert_eq!(codes(&map), ["spawn_blocked"]);
+    }
+
+    #[test]
+    fn obstacles_may_not_cover_the_base() {
+        let base = Vector2::new(800.0, 800.0);
+        let map = map_with(Some(base), vec![around(base)]);
+        assert_eq!(codes(&map), ["base_blocked"]);
+    }
+
+    #[test]
+    fn obstacles_may_not_cover_locations() {
+        let map = map_with(None, vec![around(Vector2::new(500.0, 500.0))]);
+        assert_eq!(codes(&map), ["blocked"]);
+        assert_eq!(validate_map(&map)[0].index(), Some(0));
+    }
+
+    #[test]
+    fn locations_must_be_inside_the_playable_area() {
+        let mut map = map_with(None, Vec::new());
+        map.locations.push(ResourceLocation {
+            x: BORDER_WIDTH - 1.0,
+            y: 500.0,
+            rarity: 0,
+        });
+        map.locations.push(ResourceLocation {
+            x: 500.0,
+            y: MAP_HEIGHT - BORDER_WIDTH,
+            rarity: 0,
+        });
+        let issues = validate_map(&map);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(issues[0], MapIssue::OutOfBounds { index: 1, .. }));
+    }
+
+    #[test]
+    fn rarities_must_be_known() {
+        let mut map = map_with(None, Vec::new());
+        map.locations[0].rarity = tiers::tiers().len() as u8;
+        assert_eq!(codes(&map), ["unknown_rarity"]);
+        assert_eq!(validate_map(&map)[0].index(), Some(0));
+    }
+
+    #[test]
+    fn duplicates_name_the_first_location() {
+        let mut map = map_with(None, Vec::new());
+        map.locations.push(ResourceLocation {
+            x: 600.0,
+            y: 600.0,
+            rarity: 0,
+        });
+        map.locations.push(map.locations[0].clone());
+        let issues = validate_map(&map);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            issues[0],
+            MapIssue::Duplicate { index: 2, first: 0 }
+        ));
+    }
+
+    #[test]
+    fn tiers_are_capped() {
+        let rarity = 4;
+        let cap = tiers::tier(rarity).and_then(|tier| tier.cap).unwrap() as usize;
+        let mut map = map_with(None, Vec::new());
+        for offset in 0..=cap {
+            map.locations.push(ResourceLocation {
+                x: 1000.0 + 100.0 * offset as f64,
+                y: 1000.0,
+                rarity,
+            });
+        }
+        let issues = validate_map(&map);
+        assert_eq!(issues.len(), 1);
+        assert!(matches!(
+            issues[0],
+            MapIssue::TooManyOfTier { rarity: 4, count, max } if count == cap + 1 && max == cap
+        ));
+
+        // Tiers without a cap take any number.
+        for offset in 0..50 {
+            map.locations.push(ResourceLocation {
+                x: 1000.0 + 10.0 * offset as f64,
+                y: 2000.0,
+                rarity: 0,
+            });
+        }
+        assert_eq!(validate_map(&map).len(), 1);
+    }
+
+    #[test]
+    fn issues_point_into_the_right_list() {
+        let mut map = map_with(None, vec![around(Vector2::new(1000.0, 400.0))]);
+        map.obstacles.push(Obstacle {
+            x: 100.0,
+            y: 100.0,
+            width: 0.0,
+            height: 10.0,
+        });
+        let issues = validate_map(&map);
+        assert_eq!(codes(&map), ["invalid_obstacle"]);
+        let issue = serde_json::to_value(&issues[0]).unwrap();
+        assert_eq!(issue["index"], serde_json::Value::Null);
+        assert_eq!(issue["obstacle"], 1);
+
+        let map = map_with(None, vec![around(Vector2::new(500.0, 500.0))]);
+        let issue = serde_json::to_value(&validate_map(&map)[0]).unwrap();
+        assert_eq!(issue["index"], 0);
+        assert_eq!(issue["obstacle"], 0);
+    }
+
+    #[test]
+    fn clear_obstacles_are_accepted() {
+        let map = map_with(
+            Some(Vector2::new(800.0, 800.0)),
+            vec![around(Vector2::new(1000.0, 400.0))],
+        );
+        assert!(validate_map(&map).is_empty());
+    }
+}
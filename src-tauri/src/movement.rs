@@ -0,0 +1,60 @@
+use crate::constants::PLAYER_SPEED;
+use crate::pathfinding::WalkGrid;
+use crate::vector2::Vector2;
+use crate::world::{clamp_to_playable, WorldError};
+
+/// Distance at which a walking player snaps onto its target.
+const ARRIVAL_DISTANCE: f64 = 5.0;
+
+/// Where a player is and where it is walking. The server and the client's
+/// prediction move players with the same code, so they only disagree when
+/// their inputs do.
+#[derive(Clone, Default)]
+pub struct Motion {
+    pub position: Vector2,
+    /// Point the player is walking towards, if any.
+    pub target: Option<Vector2>,
+    /// Remaining waypoints to `target`, ending with it.
+    pub path: Vec<Vector2>,
+}
+
+impl Motion {
+    /// Starts walking towards `target`, clamped to the playable area, along
+    /// a path around obstacles.
+    pub fn walk_to(&mut self, grid: &WalkGrid, target: Vector2) -> Result<(), WorldError> {
+        let target = clamp_to_playable(target);
+        self.path = grid
+            .find_path(self.position, target)
+            .ok_or(WorldError::NoPath {
+                x: target.x,
+                y: target.y,
+            })?;
+        self.target = Some(target);
+        Ok(())
+    }
+
+    /// Walks `delta_time` seconds along the path. Returns whether the player
+    /// arrived at its target during this step.
+    pub fn advance(&mut self, delta_time: f64) -> bool {
+        let Some(target) = self.target else {
+            return false;
+        };
+        let waypoint = self.path.first().copied().unwrap_or(target);
+        let distance = self.position.distance_to(waypoint);
+        let step = PLAYER_SPEED * delta_time;
+        if distance < ARRIVAL_DISTANCE || distance <= step {
+            self.position = waypoint;
+            if !self.path.is_empty() {
+                self.path.remove(0);
+            }
+            if self.path.is_empty() {
+                self.target = None;
+                return true;
+            }
+        } else {
+            let direction = (waypoint - self.position).normalize();
+            self.position = clamp_to_playable(self.position + direction.multiply(step));
+        }
+        false
+    }
+}
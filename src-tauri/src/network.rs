@@ -0,0 +1,536 @@
+use crate::discovery;
+use crate::generator::{self, GeneratorParams};
+use crate::interest::{self, Interest, Overview};
+use crate::map::{self, MapData};
+use crate::sync::{SyncEncoder, SyncFrame};
+use crate::tick;
+use crate::tools;
+use crate::vector2::Vector2;
+use crate::world::{GatherLease, PlayerState, World, WorldDelta, WorldError, WorldEvent};
+use futures_util::{SinkExt, StreamExt};
+use serde::{Deserialize, Serialize};
+use std::net::SocketAddr;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use tokio::net::{TcpListener, TcpStream};
+use tokio::sync::broadcast::{self, error::RecvError};
+use tokio_tungstenite::tungstenite::Message;
+
+pub const DEFAULT_SERVER_PORT: u16 = 7777;
+
+/// Messages buffered per client before it is considered lagging and gets a
+/// full snapshot instead.
+const BROADCAST_CAPACITY: usize = 256;
+
+#[derive(Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ClientMessage {
+    Join {
+        player_id: String,
+        display_name: String,
+    },
+    MoveTo {
+        x: f64,
+        y: f64,
+        /// Sequence number of a predicted input, echoed as the player's
+        /// `lastInput` once applied.
+        input: Option<u64>,
+    },
+    StartGathering {
+        resource_id: String,
+    },
+    StopGathering,
+    /// Moves the client's area of interest. Width and height default to the
+    /// canvas size.
+    SetCamera {
+        x: f64,
+        y: f64,
+        width: Option<f64>,
+        height: Option<f64>,
+    },
+    /// Confirms the client applied sync frame `sequence`. Not answered.
+    AckSync {
+        sequence: u64,
+    },
+    /// Asks for a full sync frame after the client lost track of its state.
+    RequestSnapshot,
+    Heartbeat,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum ServerMessage {
+    Ack,
+    LeaseGranted { lease: GatherLease },
+    Welcome { player_id: String, frame: SyncFrame },
+    Sync { frame: SyncFrame },
+    Overview { overview: Overview },
+    PlayerJoined { player: PlayerState },
+    PlayerLeft { player_id: String },
+    Error { code: String, message: String },
+}
+
+impl ServerMessage {
+    fn error(code: &str, message: impl Into<String>) -> Self {
+        ServerMessage::Error {
+            code: code.to_string(),
+            message: message.into(),
+        }
+    }
+
+    fn encode(&self) -> String {
+        serde_json::to_string(self).expect("server messages always serialize")
+    }
+}
+
+impl From<WorldError> for ServerMessage {
+    fn from(error: WorldError) -> Self {
+        ServerMessage::error(error.code(), error.to_string())
+    }
+}
+
+/// What the tick loop and connections publish to every connection.
+#[derive(Clone)]
+enum Update {
+    /// A simulation step, narrowed per client to its area of interest.
+    Step(Arc<WorldDelta>),
+    /// An encoded message every client receives as is.
+    Message(String),
+}
+
+/// Per-connection state.
+#[derive(Default)]
+struct Connection {
+    player_id: Option<String>,
+    interest: Interest,
+    sync: SyncEncoder,
+}
+
+impl Connection {
+    /// The next sync frame for this client, `None` before it joined or when
+    /// it is up to date. Frames are full after `reset`.
+    fn sync_frame(&mut self, world: &World, events: &[WorldEvent]) -> Option<SyncFrame> {
+        let id = self.player_id.as_deref()?;
+        self.sync
+            .encode(world.tick(), &self.interest.snapshot(world, id), events)
+    }
+}
+
+pub struct ServerConfig {
+    pub bind: SocketAddr,
+    /// Map file to host. Falls back to `seed`, then to the bundled map.
+    pub map_path: Option<PathBuf>,
+    pub seed: Option<u64>,
+    /// Session name shown in LAN session lists.
+    pub name: String,
+    /// Where to announce the session, usually `discovery::broadcast_address`.
+    /// `None` keeps the session off LAN lists.
+    pub announce: Option<SocketAddr>,
+}
+
+/// Map name announced to the LAN.
+fn map_label(config: &ServerConfig) -> String {
+    if let Some(path) = &config.map_path {
+        return path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_else(|| path.display().to_string());
+    }
+    match config.seed {
+        Some(seed) => format!("Generated ({})", seed),
+        None => "Default".to_string(),
+    }
+}
+
+pub fn load_server_map(config: &ServerConfig) -> Result<MapData, String> {
+    if let Some(path) = &config.map_path {
+        return map::load_map_file(path).map_err(|e| e.to_string());
+    }
+    if let Some(seed) = config.seed {
+        return generator::generate(seed, &GeneratorParams::default());
+    }
+    map::parse_map(map::DEFAULT_MAP).map_err(|e| e.to_string())
+}
+
+/// Hosts a session until the process is stopped.
+pub async fn run_server(config: ServerConfig) -> Result<(), String> {
+    let map = load_server_map(&config)?;
+    let mut world = World::new();
+    world.load_map(&map);
+    world.set_tools(tools::parse_tools(tools::DEFAULT_TOOLS)?);
+    let listener = TcpListener::bind(config.bind)
+        .await
+        .map_err(|e| format!("Failed to bind {}: {}", config.bind, e))?;
+    let world = Arc::new(Mutex::new(world));
+    if let Some(target) = config.announce {
+        let port = listener
+            .local_addr()
+            .map_err(|e| format!("Failed to read bound address: {}", e))?
+            .port();
+        let announcer = discovery::announce(
+            target,
+            config.name.clone(),
+            map_label(&config),
+            port,
+            world.clone(),
+        );
+        tokio::spawn(async move {
+            if let Err(e) = announcer.await {
+                println!("LAN announcements stopped: {}", e);
+            }
+        });
+    }
+    serve(listener, world).await
+}
+
+/// Runs the simulation and accepts WebSocket clients on `listener`. Split from
+/// `run_server` so callers can bind an ephemeral port themselves.
+pub async fn serve(listener: TcpListener, world: Arc<Mutex<World>>) -> Result<(), String> {
+    let (updates, _) = broadcast::channel(BROADCAST_CAPACITY);
+
+    let tick_world = world.clone();
+    let tick_updates = updates.clone();
+    tokio::spawn(tick::run_loop(move |tick, delta_time| {
+        let mut world = tick_world.lock().unwrap();
+        let delta = world.step(tick, delta_time);
+        // Sending only fails while nobody is connected.
+        if !delta.is_empty() {
+            let _ = tick_updates.send(Update::Step(Arc::new(delta)));
+        }
+        if let Some(overview) = interest::scheduled_overview(&world, tick) {
+            let _ = tick_updates.send(Update::Message(
+                ServerMessage::Overview { overview }.encode(),
+            ));
+        }
+    }));
+
+    loop {
+        let (stream, address) = listener
+            .accept()
+            .await
+            .map_err(|e| format!("Failed to accept connection: {}", e))?;
+        tokio::spawn(handle_connection(
+            stream,
+            address,
+            world.clone(),
+            updates.clone(),
+        ));
+    }
+}
+
+async fn handle_connection(
+    stream: TcpStream,
+    address: SocketAddr,
+    world: Arc<Mutex<World>>,
+    updates: broadcast::Sender<Update>,
+) {
+    let socket = match tokio_tungstenite::accept_async(stream).await {
+        Ok(socket) => socket,
+        Err(e) => {
+            println!("WebSocket handshake with {} failed: {}", address, e);
+            return;
+        }
+    };
+    println!("Client connected from {}", address);
+
+    let (mut sink, mut source) = socket.split();
+    let mut receiver = updates.subscribe();
+    let mut connection = Connection::default();
+
+    loop {
+        let outgoing = tokio::select! {
+            incoming = source.next() => match incoming {
+                Some(Ok(Message::Text(text))) => {
+                    match handle_message(text.as_str(), &world, &updates, &mut connection) {
+                        Some(reply) => reply.encode(),
+                        None => continue,
+                    }
+                }
+                Some(Ok(Message::Close(_))) | Some(Err(_)) | None => break,
+                Some(Ok(_)) => continue,
+            },
+            update = receiver.recv() => match update {
+                Ok(Update::Message(text)) => text,
+                Ok(Update::Step(delta)) => {
+                    match connection.sync_frame(&world.lock().unwrap(), &delta.events) {
+                        Some(frame) => ServerMessage::Sync { frame }.encode(),
+                        None => continue,
+                    }
+                }
+                Err(RecvError::Lagged(_)) => {
+                    connection.sync.reset();
+                    match connection.sync_frame(&world.lock().unwrap(), &[]) {
+                        Some(frame) => ServerMessage::Sync { frame }.encode(),
+                        None => continue,
+                    }
+                }
+                Err(RecvError::Closed) => break,
+            },
+        };
+        if sink.send(Message::text(outgoing)).await.is_err() {
+            break;
+        }
+    }
+
+    if let Some(player_id) = connection.player_id {
+        world.lock().unwrap().leave(&player_id);
+        let _ = updates.send(Update::Message(
+            ServerMessage::PlayerLeft { player_id }.encode(),
+        ));
+    }
+    println!("Client {} disconnected", address);
+}
+
+/// Applies one client message and returns the reply for that client, if it
+/// gets one. Changes other clients need to see go out through `updates`.
+fn handle_message(
+    text: &str,
+    world: &Mutex<World>,
+    updates: &broadcast::Sender<Update>,
+    connection: &mut Connection,
+) -> Option<ServerMessage> {
+    let message: ClientMessage = match serde_json::from_str(text) {
+        Ok(message) => message,
+        Err(e) => return Some(ServerMessage::error("bad_message", e.to_string())),
+    };
+    let mut world = world.lock().unwrap();
+
+    let (message, id) = match (message, connection.player_id.clone()) {
+        (
+            ClientMessage::Join {
+                player_id: id,
+                display_name,
+            },
+            None,
+        ) => return Some(join(&mut world, updates, connection, id, &display_name)),
+        (ClientMessage::Join { .. }, Some(_)) => {
+            return Some(ServerMessage::error(
+                "already_joined",
+                "This connection already joined",
+            ))
+        }
+        (_, None) => {
+            return Some(ServerMessage::error(
+                "not_joined",
+                "Send a join message first",
+            ))
+        }
+        (message, Some(id)) => (message, id),
+    };
+    let result = match message {
+        ClientMessage::MoveTo { x, y, input } => {
+            // Rejected inputs are acknowledged too, the client then falls
+            // back to the authoritative state.
+            if let Some(input) = input {
+                let _ = world.acknowledge_input(&id, input);
+            }
+            world.set_move_target(&id, Vector2::new(x, y))
+        }
+        ClientMessage::StartGathering { resource_id } => {
+            return Some(match world.start_gathering(&id, &resource_id) {
+                Ok(lease) => ServerMessage::LeaseGranted { lease },
+                Err(e) => e.into(),
+            })
+        }
+        ClientMessage::Heartbeat => world.touch(&id),
+        ClientMessage::StopGathering => {
+            world.stop_gathering(&id);
+            Ok(())
+        }
+        ClientMessage::SetCamera {
+            x,
+            y,
+            width,
+            height,
+        } => {
+            let viewport = width
+                .zip(height)
+                .map(|(width, height)| Vector2::new(width, height));
+            connection.interest.set_camera(Vector2::new(x, y), viewport);
+            return Some(match connection.sync_frame(&world, &[]) {
+                Some(frame) => ServerMessage::Sync { frame },
+                None => ServerMessage::Ack,
+            });
+        }
+        ClientMessage::AckSync { sequence } => {
+            connection.sync.ack(sequence);
+            return None;
+        }
+        ClientMessage::RequestSnapshot | ClientMessage::Join { .. } => {
+            connection.sync.reset();
+            return connection
+                .sync_frame(&world, &[])
+                .map(|frame| ServerMessage::Sync { frame });
+        }
+    };
+    Some(match result {
+        Ok(()) => ServerMessage::Ack,
+        Err(e) => e.into(),
+    })
+}
+
+fn join(
+    world: &mut World,
+    updates: &broadcast::Sender<Update>,
+    connection: &mut Connection,
+    id: String,
+    display_name: &str,
+) -> ServerMessage {
+    if world.has_player(&id) {
+        return ServerMessage::error(
+            "player_taken",
+            format!("Player '{}' is already connected", id),
+        );
+    }
+    world.join(&id, display_name, map::spawn_point());
+    if let Some(player) = world.player(&id) {
+        let _ = updates.send(Update::Message(
+            ServerMessage::PlayerJoined {
+                player: player.clone(),
+            }
+            .encode(),
+        ));
+    }
+    let frame = connection
+        .sync
+        .full(world.tick(), &connection.interest.snapshot(world, &id));
+    connection.player_id = Some(id.clone());
+    ServerMessage::Welcome {
+        player_id: id,
+        frame,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::ResourceLocation;
+    use futures_util::stream::{SplitSink, SplitStream};
+    use serde_json::{json, Value};
+    use tokio::time::{timeout, Duration};
+    use tokio_tungstenite::{connect_async, MaybeTlsStream, WebSocketStream};
+
+    type Socket = WebSocketStream<MaybeTlsStream<TcpStream>>;
+
+    async fn start_server() -> SocketAddr {
+        let mut world = World::new();
+        world.load_map(&MapData {
+            locations: vec![ResourceLocation {
+                x: 600.0,
+                y: 600.0,
+                rarity: 0,
+            }],
+            base: None,
+            obstacles: Vec::new(),
+        });
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let address = listener.local_addr().unwrap();
+        tokio::spawn(serve(listener, Arc::new(Mutex::new(world))));
+        address
+    }
+
+    async fn send(sink: &mut SplitSink<Socket, Message>, message: Value) {
+        sink.send(Message::text(message.to_string())).await.unwrap();
+    }
+
+    /// The next message of type `kind`, skipping broadcasts such as
+    /// overviews that may arrive in between.
+    async fn receive(source: &mut SplitStream<Socket>, kind: &str) -> Value {
+        timeout(Duration::from_secs(5), async {
+            loop {
+                let message = source.next().await.unwrap().unwrap();
+                let Message::Text(text) = message else {
+                    continue;
+                };
+                let message: Value = serde_json::from_str(text.as_str()).unwrap();
+                if message["type"] == kind {
+                    return message;
+                }
+            }
+        })
+        .await
+        .unwrap_or_else(|_| panic!("no {} message received", kind))
+    }
+
+    #[tokio::test]
+    async fn clients_join_and_receive_their_moves() {
+        let address = start_server().await;
+        let (socket, _) = connect_async(format!("ws://{}", address)).await.unwrap();
+        let (mut sink, mut source) = socket.split();
+
+        send(&mut sink, json!({ "type": "moveTo", "x": 1.0, "y": 1.0 })).await;
+        assert_eq!(receive(&mut source, "error").await["code"], "not_joined");
+
+        send(
+            &mut sink,
+            json!({ "type": "join", "playerId": "a", "displayName": "A" }),
+        )
+        .await;
+        let welcome = receive(&mut source, "welcome").await;
+        assert_eq!(welcome["playerId"], "a");
+        let frame = &welcome["frame"];
+        assert!(frame["baseline"].is_null());
+        let players = frame["players"].as_array().unwrap();
+        assert!(players.iter().any(|player| player["id"] == "a"));
+        let sequence = frame["sequence"].as_u64().unwrap();
+        send(
+            &mut sink,
+            json!({ "type": "ackSync", "sequence": sequence }),
+        )
+        .await;
+
+        let spawn = map::spawn_point();
+        send(
+            &mut sink,
+            json!({ "type": "moveTo", "x": spawn.x + 100.0, "y": spawn.y, "input": 1 }),
+        )
+        .await;
+        receive(&mut source, "ack").await;
+
+        // Frames sent before the move was applied may still be in flight.
+        loop {
+            let sync = receive(&mut source, "sync").await;
+            let frame = &sync["frame"];
+            assert!(frame["sequence"].as_u64().unwrap() > sequence);
+            let Some(own) = frame["players"]
+                .as_array()
+                .and_then(|players| players.iter().find(|player| player["id"] == "a"))
+            else {
+                continue;
+            };
+            let x = own["fields"]["position"]["x"].as_f64();
+            if x.is_some_and(|x| x > spawn.x) {
+                // Frames build on the acked welcome, so the input shows too.
+                assert_eq!(own["fields"]["lastInput"], 1);
+                break;
+            }
+        }
+    }
+
+    #[tokio::test]
+    async fn player_ids_cannot_be_taken_twice() {
+        let address = start_server().await;
+        let join = json!({ "type": "join", "playerId": "a", "displayName": "A" });
+
+        let (first, _) = connect_async(format!("ws://{}", address)).await.unwrap();
+        let (mut first_sink, mut first_source) = first.split();
+        send(&mut first_sink, join.clone()).await;
+        receive(&mut first_source, "welcome").await;
+
+        let (second, _) = connect_async(format!("ws://{}", address)).await.unwrap();
+        let (mut second_sink, mut second_source) = second.split();
+        send(&mut second_sink, join).await;
+        assert_eq!(
+            receive(&mut second_source, "error").await["code"],
+            "player_taken"
+        );
+    }
+}
@@ -0,0 +1,326 @@
+use crate::constants::{BORDER_WIDTH, MAP_HEIGHT, MAP_WIDTH};
+use crate::map::{MapData, Obstacle};
+use crate::vector2::Vector2;
+#[cfg(feature = "gui")]
+use crate::world::{World, WorldError};
+use std::cmp::Reverse;
+use std::collections::BinaryHeap;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+/// Side length of a grid cell. Matches the border so the border is exactly
+/// one ring of blocked cells.
+pub const GRID_CELL_SIZE: f64 = BORDER_WIDTH;
+
+/// Step costs scaled to integers so the open set can be a plain heap.
+const STRAIGHT_COST: u32 = 10;
+const DIAGONAL_COST: u32 = 14;
+
+/// Walkability of the map, derived from its border and obstacles.
+#[derive(Default)]
+pub struct WalkGrid {
+    columns: usize,
+    rows: usize,
+    blocked: Vec<bool>,
+    obstacles: Vec<Obstacle>,
+}
+
+impl WalkGrid {
+    /// A cell is blocked when its center lies outside the playable area or
+    /// any obstacle overlaps it.
+    pub fn from_map(map: &MapData) -> Self {
+        let columns = (MAP_WIDTH / GRID_CELL_SIZE).ceil() as usize;
+        let rows = (MAP_HEIGHT / GRID_CELL_SIZE).ceil() as usize;
+        let mut grid = Self {
+            columns,
+            rows,
+            blocked: vec![false; columns * rows],
+            obstacles: map.obstacles.clone(),
+        };
+        for row in 0..rows {
+            for column in 0..columns {
+                let min = Vector2::new(column as f64 * GRID_CELL_SIZE, row as f64 * GRID_CELL_SIZE);
+                let max = Vector2::new(min.x + GRID_CELL_SIZE, min.y + GRID_CELL_SIZE);
+                let center = grid.center(column, row);
+                grid.blocked[row * columns + column] = !in_playable_area(center)
+                    || grid
+                        .obstacles
+                        .iter()
+                        .any(|obstacle| obstacle.overlaps(min, max));
+            }
+        }
+        grid
+    }
+
+    /// Whether a player may stand at `point`.
+    pub fn is_walkable(&self, point: Vector2) -> bool {
+        in_playable_area(point)
+            && !self
+                .obstacles
+                .iter()
+                .any(|obstacle| obstacle.contains(point))
+    }
+
+    /// Whether walking straight from `from` to `to` stays clear of obstacles.
+    /// The playable area is convex, so only the obstacles need checking.
+    pub fn segment_clear(&self, from: Vector2, to: Vector2) -> bool {
+        !self
+            .obstacles
+            .iter()
+            .any(|obstacle| obstacle.intersects_segment(from, to))
+    }
+
+    fn cell_of(&self, point: Vector2) -> usize {
+        let column = ((point.x / GRID_CELL_SIZE).max(0.0) as usize).min(self.columns - 1);
+        let row = ((point.y / GRID_CELL_SIZE).max(0.0) as usize).min(self.rows - 1);
+        row * self.columns + column
+    }
+
+    fn center(&self, column: usize, row: usize) -> Vector2 {
+        Vector2::new(
+            (column as f64 + 0.5) * GRID_CELL_SIZE,
+            (row as f64 + 0.5) * GRID_CELL_SIZE,
+        )
+    }
+
+    /// Waypoints from `from` to `to`, ending exactly at `to` and excluding
+    /// `from`. `None` when `to` cannot be stood on or reached.
+    pub fn find_path(&self, from: Vector2, to: Vector2) -> Option<Vec<Vector2>> {
+        if !self.is_walkable(to) {
+            return None;
+        }
+        if self.columns == 0 || self.segment_clear(from, to) {
+            return Some(vec![to]);
+        }
+
+        let start = self.cell_of(from);
+        let goal = self.cell_of(to);
+        // The exact start and goal points are walkable even when part of
+        // their cell is covered.
+        let passable = |cell: usize| cell == start || cell == goal || !self.blocked[cell];
+        let cells = self.search(start, goal, passable)?;
+
+        let mut waypoints: Vec<Vector2> = cells
+            .iter()
+            .skip(1)
+            .take(cells.len().saturating_sub(2))
+            .map(|&cell| self.center(cell % self.columns, cell / self.columns))
+            .collect();
+        waypoints.push(to);
+        Some(self.smooth(from, waypoints))
+    }
+
+    /// A* over grid cells with 8-way moves. Diagonals may not cut past a
+    /// blocked orthogonal neighbour.
+    fn search(
+        &self,
+        start: usize,
+        goal: usize,
+        passable: impl Fn(usize) -> bool,
+    ) -> Option<Vec<usize>> {
+        let (goal_column, goal_row) = (goal % self.columns, goal / self.columns);
+        let heuristic = |cell: usize| {
+            let dx = (cell % self.columns).abs_diff(goal_column) as u32;
+            let dy = (cell / self.columns).abs_diff(goal_row) as u32;
+            STRAIGHT_COST * dx.max(dy) + (DIAGONAL_COST - STRAIGHT_COST) * dx.min(dy)
+        };
+
+        let mut cost = vec![u32::MAX; self.blocked.len()];
+        let mut came_from = vec![usize::MAX; self.blocked.len()];
+        let mut open = BinaryHeap::new();
+        cost[start] = 0;
+        open.push(Reverse((heuristic(start), start)));
+
+        while let Some(Reverse((_, cell))) = open.pop() {
+            if cell == goal {
+                let mut path = vec![goal];
+                let mut current = goal;
+                while current != start {
+                    current = came_from[current];
+                    path.push(current);
+                }
+                path.reverse();
+                return Some(path);
+            }
+            let (column, row) = (
+                (cell % self.columns) as isize,
+                (cell / self.columns) as isize,
+            );
+            for (dx, dy) in NEIGHBOURS {
+                let (next_column, next_row) = (column + dx, row + dy);
+                if next_column < 0
+                    || next_row < 0
+                    || next_column >= self.columns as isize
+                    || next_row >= self.rows as isize
+                {
+                    continue;
+                }
+                let next = next_row as usize * self.columns + next_column as usize;
+                if !passable(next) {
+                    continue;
+                }
+                let diagonal = dx != 0 && dy != 0;
+                if diagonal
+                    && !(passable(row as usize * self.columns + next_column as usize)
+                        && passable(next_row as usize * self.columns + column as usize))
+                {
+                    continue;
+                }
+                let step = if diagonal {
+                    DIAGONAL_COST
+                } else {
+                    STRAIGHT_COST
+                };
+                let next_cost = cost[cell] + step;
+                if next_cost < cost[next] {
+                    cost[next] = next_cost;
+                    came_from[next] = cell;
+                    open.push(Reverse((next_cost + heuristic(next), next)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Drops every waypoint that can be skipped by walking straight to a
+    /// later one.
+    fn smooth(&self, from: Vector2, waypoints: Vec<Vector2>) -> Vec<Vector2> {
+        let mut smoothed = Vec::new();
+        let mut current = from;
+        let mut index = 0;
+        while index < waypoints.len() {
+            let next = (index..waypoints.len())
+                .rev()
+                .find(|&candidate| self.segment_clear(current, waypoints[candidate]))
+                .unwrap_or(index);
+            current = waypoints[next];
+            smoothed.push(current);
+            index = next + 1;
+        }
+        smoothed
+    }
+}
+
+const NEIGHBOURS: [(isize, isize); 8] = [
+    (-1, -1),
+    (0, -1),
+    (1, -1),
+    (-1, 0),
+    (1, 0),
+    (-1, 1),
+    (0, 1),
+    (1, 1),
+];
+
+fn in_playable_area(point: Vector2) -> bool {
+    (BORDER_WIDTH..=MAP_WIDTH - BORDER_WIDTH).contains(&point.x)
+        && (BORDER_WIDTH..=MAP_HEIGHT - BORDER_WIDTH).contains(&point.y)
+}
+
+/// Waypoints the movement simulation would follow from `from` to `to`.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn find_path(
+    world: State<'_, Mutex<World>>,
+    from: Vector2,
+    to: Vector2,
+) -> Result<Vec<Vector2>, WorldError> {
+    world.lock().unwrap().find_path(from, to)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn grid(obstacles: &[(f64, f64, f64, f64)]) -> WalkGrid {
+        WalkGrid::from_map(&MapData {
+            locations: Vec::new(),
+            base: None,
+            obstacles: obstacles
+                .iter()
+                .map(|&(x, y, width, height)| Obstacle {
+                    x,
+                    y,
+                    width,
+                    height,
+                })
+                .collect(),
+        })
+    }
+
+    /// Every leg of the path, sampled every pixel, stays on walkable ground.
+    fn assert_walkable(grid: &WalkGrid, from: Vector2, path: &[Vector2]) {
+        let mut current = from;
+        for &waypoint in path {
+            assert!(grid.segment_clear(current, waypoint));
+            let steps = current.distance_to(waypoint).ceil() as usize;
+            for step in 0..=steps {
+                let amount = step as f64 / steps.max(1) as f64;
+                assert!(grid.is_walkable(current + (waypoint - current).multiply(amount)));
+            }
+            current = waypoint;
+        }
+    }
+
+    #[test]
+    fn open_ground_is_walked_straight() {
+        let grid = grid(&[]);
+        let to = Vector2::new(2000.0, 1500.0);
+        assert_eq!(
+            grid.find_path(Vector2::new(500.0, 500.0), to),
+            Some(vec![to])
+        );
+    }
+
+    #[test]
+    fn paths_route_around_obstacles() {
+        let grid = grid(&[(900.0, 300.0, 100.0, 1400.0)]);
+        let (from, to) = (Vector2::new(700.0, 1000.0), Vector2::new(1200.0, 1000.0));
+        let path = grid.find_path(from, to).unwrap();
+        assert!(path.len() > 1);
+        assert_eq!(path.last(), Some(&to));
+        assert_walkable(&grid, from, &path);
+    }
+
+    #[test]
+    fn smoothed_paths_never_cut_through_blocked_cells() {
+        // A zigzag of walls forcing several turns.
+        let grid = grid(&[
+            (600.0, 50.0, 100.0, 2000.0),
+            (1200.0, 1000.0, 100.0, 1950.0),
+            (1800.0, 50.0, 100.0, 2000.0),
+        ]);
+        let (from, to) = (Vector2::new(300.0, 2500.0), Vector2::new(2500.0, 2500.0));
+        let path = grid.find_path(from, to).unwrap();
+        assert!(path.len() >= 3);
+        assert_eq!(path.last(), Some(&to));
+        for waypoint in &path[..path.len() - 1] {
+            assert!(!grid.blocked[grid.cell_of(*waypoint)]);
+        }
+        assert_walkable(&grid, from, &path);
+    }
+
+    #[test]
+    fn blocked_targets_have_no_path() {
+        let grid = grid(&[(900.0, 900.0, 200.0, 200.0)]);
+        let from = Vector2::new(500.0, 500.0);
+        assert_eq!(grid.find_path(from, Vector2::new(1000.0, 1000.0)), None);
+        assert_eq!(grid.find_path(from, Vector2::new(20.0, 500.0)), None);
+    }
+
+    #[test]
+    fn enclosed_targets_are_unreachable() {
+        let grid = grid(&[
+            (1300.0, 1300.0, 400.0, 100.0),
+            (1300.0, 1600.0, 400.0, 100.0),
+            (1300.0, 1300.0, 100.0, 400.0),
+            (1600.0, 1300.0, 100.0, 400.0),
+        ]);
+        let inside = Vector2::new(1500.0, 1500.0);
+        assert!(grid.is_walkable(inside));
+        assert_eq!(grid.find_path(Vector2::new(500.0, 500.0), inside), None);
+        assert_eq!(grid.find_path(inside, Vector2::new(500.0, 500.0)), None);
+    }
+}
@@ -0,0 +1,313 @@
+use crate::map::MapData;
+use crate::movement::Motion;
+use crate::pathfinding::WalkGrid;
+use crate::sync::{SyncDecoder, SyncError, SyncFrame};
+use crate::tick::TICK_RATE;
+use crate::vector2::Vector2;
+use crate::world::WorldError;
+use serde::Deserialize;
+use serde_json::Value;
+use std::collections::{BTreeMap, VecDeque};
+
+/// How far behind the newest server state remote players are shown, in
+/// ticks, so there is usually a newer position to interpolate towards.
+pub const INTERPOLATION_DELAY: f64 = 3.0;
+
+/// Positions kept per remote player, a little over one second's worth.
+const MAX_SAMPLES: usize = TICK_RATE as usize + 4;
+
+/// A move applied locally that the server has not acknowledged yet.
+struct PendingInput {
+    sequence: u64,
+    /// Estimated server tick the input was made at.
+    tick: f64,
+    target: Vector2,
+}
+
+/// The local player's fields prediction restarts from.
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct Authoritative {
+    position: Vector2,
+    target: Option<Vector2>,
+    #[serde(default)]
+    path: Vec<Vector2>,
+    #[serde(default)]
+    last_input: u64,
+}
+
+/// Client side of a networked session. Moves the local player ahead of the
+/// server with the server's own movement code, corrects it whenever newer
+/// authoritative state arrives, and interpolates remote players between the
+/// positions the server sent.
+pub struct Prediction {
+    player_id: String,
+    grid: WalkGrid,
+    decoder: SyncDecoder,
+    motion: Motion,
+    next_input: u64,
+    pending: VecDeque<PendingInput>,
+    /// Newest frame the prediction was reconciled with.
+    reconciled: u64,
+    /// Estimated current server tick, fractional between frames.
+    server_time: f64,
+    remote: BTreeMap<String, VecDeque<(f64, Vector2)>>,
+}
+
+/// Walks `ticks` simulation steps the way the server's tick loop does.
+fn simulate(motion: &mut Motion, ticks: f64) {
+    let step = 1.0 / TICK_RATE as f64;
+    let mut remaining = ticks.max(0.0);
+    while remaining >= 1.0 {
+        motion.advance(step);
+        remaining -= 1.0;
+    }
+    if remaining > 0.0 {
+        motion.advance(remaining * step);
+    }
+}
+
+impl Prediction {
+    pub fn new(player_id: &str, map: &MapData) -> Self {
+        Self {
+            player_id: player_id.to_string(),
+            grid: WalkGrid::from_map(map),
+            decoder: SyncDecoder::default(),
+            motion: Motion::default(),
+            next_input: 0,
+            pending: VecDeque::new(),
+            reconciled: 0,
+            server_time: 0.0,
+            remote: BTreeMap::new(),
+        }
+    }
+
+    /// Predicted state of the local player.
+    pub fn motion(&self) -> &Motion {
+        &self.motion
+    }
+
+    /// Starts walking towards `target` locally. Returns the sequence number
+    /// to send as the move's `input`.
+    pub fn move_to(&mut self, target: Vector2) -> Result<u64, WorldError> {
+        self.motion.walk_to(&self.grid, target)?;
+        self.next_input += 1;
+        self.pending.push_back(PendingInput {
+            sequence: self.next_input,
+            tick: self.server_time,
+            target,
+        });
+        Ok(self.next_input)
+    }
+
+    /// Advances the local player and the estimated server clock.
+    pub fn advance(&mut self, delta_time: f64) {
+        self.motion.advance(delta_time);
+        self.server_time += delta_time * TICK_RATE as f64;
+    }
+
+    /// Applies a frame from the server and returns the sequence to
+    /// acknowledge. On an error the client should request a snapshot.
+    pub fn receive(&mut self, frame: &SyncFrame) -> Result<u64, SyncError> {
+        let state = self.decoder.apply(frame)?;
+        let mut own = None;
+        let mut positions = Vec::new();
+        for (id, fields) in state.players() {
+            if *id == self.player_id {
+                own = serde_json::from_value::<Authoritative>(Value::Object(fields.clone())).ok();
+            } else if let Some(position) = fields
+                .get("position")
+                .and_then(|position| serde_json::from_value(position.clone()).ok())
+            {
+                positions.push((id.clone(), position));
+            }
+        }
+
+        let tick = frame.tick as f64;
+        self.server_time = self.server_time.max(tick);
+        self.remote
+            .retain(|id, _| positions.iter().any(|(other, _)| other == id));
+        for (id, position) in positions {
+            let samples = self.remote.entry(id).or_default();
+            // Frames sent between steps carry the tick of the last step.
+            if samples.back().is_some_and(|(last, _)| *last >= tick) {
+                samples.pop_back();
+            }
+            samples.push_back((tick, position));
+            if samples.len() > MAX_SAMPLES {
+                samples.pop_front();
+            }
+        }
+
+        if frame.sequence > self.reconciled || frame.baseline.is_none() {
+            self.reconciled = frame.sequence;
+            if let Some(own) = own {
+                self.reconcile(own, tick);
+            }
+        }
+        Ok(frame.sequence)
+    }
+
+    /// Restarts the prediction from the server's state at `tick` and replays
+    /// the inputs the server had not applied yet.
+    fn reconcile(&mut self, own: Authoritative, tick: f64) {
+        while self
+            .pending
+            .front()
+            .is_some_and(|input| input.sequence <= own.last_input)
+        {
+            self.pending.pop_front();
+        }
+        self.motion = Motion {
+            position: own.position,
+            target: own.target,
+            path: own.path,
+        };
+        let mut time = tick;
+        for input in &self.pending {
+            simulate(&mut self.motion, input.tick - time);
+            time = time.max(input.tick);
+            // The server will reject it the same way, nothing to replay.
+            let _ = self.motion.walk_to(&self.grid, input.target);
+        }
+        simulate(&mut self.motion, self.server_time - time);
+    }
+
+    /// Where remote player `player_id` should be drawn, `INTERPOLATION_DELAY`
+    /// ticks behind the estimated server time.
+    pub fn interpolated_position(&self, player_id: &str) -> Option<Vector2> {
+        let samples = self.remote.get(player_id)?;
+        let time = self.server_time - INTERPOLATION_DELAY;
+        match samples.iter().position(|(tick, _)| *tick >= time) {
+            None => samples.back().map(|(_, position)| *position),
+            Some(0) => samples.front().map(|(_, position)| *position),
+            Some(index) => {
+                let (from_tick, from) = samples[index - 1];
+                let (to_tick, to) = samples[index];
+                let amount = (time - from_tick) / (to_tick - from_tick);
+                Some(from + (to - from).multiply(amount))
+            }
+        }
+    }
+
+    /// Interpolated positions of every remote player the client knows.
+    pub fn remote_positions(&self) -> BTreeMap<String, Vector2> {
+        self.remote
+            .keys()
+            .filter_map(|id| Some((id.clone(), self.interpolated_position(id)?)))
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::sync::{EntityPatch, SyncEncoder, SYNC_PROTOCOL_VERSION};
+    use crate::world::World;
+
+    const STEP: f64 = 1.0 / TICK_RATE as f64;
+
+    fn map() -> MapData {
+        MapData {
+            locations: Vec::new(),
+            base: None,
+            obstacles: Vec::new(),
+        }
+    }
+
+    fn step(world: &mut World, ticks: std::ops::RangeInclusive<u64>) {
+        for tick in ticks {
+            world.step(tick, STEP);
+        }
+    }
+
+    fn remote_frame(sequence: u64, tick: u64, x: f64) -> SyncFrame {
+        let position = serde_json::to_value(Vector2::new(x, 500.0)).unwrap();
+        SyncFrame {
+            version: SYNC_PROTOCOL_VERSION,
+            sequence,
+            baseline: None,
+            tick,
+            base: None,
+            resources: Vec::new(),
+            players: vec![EntityPatch {
+                id: "b".to_string(),
+                fields: [("position".to_string(), position)].into_iter().collect(),
+            }],
+            removed_resources: Vec::new(),
+            removed_players: Vec::new(),
+            events: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn corrections_replay_unacknowledged_inputs() {
+        let mut world = World::new();
+        world.load_map(&map());
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        let mut encoder = SyncEncoder::default();
+        let mut prediction = Prediction::new("a", &map());
+        prediction
+            .receive(&encoder.encode_reliable(0, &world.snapshot(), &[]).unwrap())
+            .unwrap();
+        assert_eq!(prediction.motion().position, Vector2::new(500.0, 500.0));
+
+        let first = prediction.move_to(Vector2::new(900.0, 500.0)).unwrap();
+        prediction.advance(5.0 * STEP);
+        let second = prediction.move_to(Vector2::new(550.0, 900.0)).unwrap();
+        prediction.advance(5.0 * STEP);
+
+        // The first input reaches the server two ticks late.
+        step(&mut world, 1..=2);
+        world
+            .set_move_target("a", Vector2::new(900.0, 500.0))
+            .unwrap();
+        world.acknowledge_input("a", first).unwrap();
+        step(&mut world, 3..=5);
+        prediction
+            .receive(&encoder.encode_reliable(5, &world.snapshot(), &[]).unwrap())
+            .unwrap();
+        assert_eq!(prediction.pending.len(), 1);
+
+        // The replayed second input lands where the server will put it.
+        world
+            .set_move_target("a", Vector2::new(550.0, 900.0))
+            .unwrap();
+        world.acknowledge_input("a", second).unwrap();
+        step(&mut world, 6..=10);
+        let snapshot = world.snapshot();
+        let server = &snapshot.players[0];
+        assert!(prediction.motion().position.distance_to(server.position) < 1e-6);
+        assert_eq!(prediction.motion().target, server.target);
+
+        prediction
+            .receive(&encoder.encode_reliable(10, &world.snapshot(), &[]).unwrap())
+            .unwrap();
+        assert!(prediction.pending.is_empty());
+        assert!(prediction.motion().position.distance_to(server.position) < 1e-6);
+    }
+
+    #[test]
+    fn remote_players_are_interpolated_between_samples() {
+        let mut prediction = Prediction::new("a", &map());
+        prediction.receive(&remote_frame(1, 10, 100.0)).unwrap();
+        prediction.receive(&remote_frame(2, 12, 200.0)).unwrap();
+        // Three ticks behind tick 12 is before the first sample.
+        assert_eq!(
+            prediction.interpolated_position("b"),
+            Some(Vector2::new(100.0, 500.0))
+        );
+
+        prediction.advance(2.0 * STEP);
+        let halfway = prediction.interpolated_position("b").unwrap();
+        assert!(halfway.distance_to(Vector2::new(150.0, 500.0)) < 1e-6);
+        assert_eq!(prediction.remote_positions().len(), 1);
+
+        prediction.advance(10.0 * STEP);
+        assert_eq!(
+            prediction.interpolated_position("b"),
+            Some(Vector2::new(200.0, 500.0))
+        );
+        assert_eq!(prediction.interpolated_position("a"), None);
+    }
+}
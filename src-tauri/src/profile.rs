@@ -0,0 +1,344 @@
+use crate::constants::{DEFAULT_PLAYER_COLOR, PLAYER_COLORS};
+use crate::storage::{self, Migration};
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::hash_map::RandomState;
+use std::fmt;
+use std::fs;
+use std::hash::{BuildHasher, Hasher};
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use tauri::State;
+
+pub const PROFILE_FILE: &str = "profile.json";
+pub const PROFILE_VERSION: u32 = 1;
+
+/// `PROFILE_MIGRATIONS[v]` upgrades a version `v` profile to `v + 1`.
+const PROFILE_MIGRATIONS: [Migration; PROFILE_VERSION as usize] = [storage::stamp_version];
+
+pub const DISPLAY_NAME_MIN_LENGTH: usize = 2;
+pub const DISPLAY_NAME_MAX_LENGTH: usize = 16;
+
+/// Languages with a `public/locales/<code>` folder.
+pub const SUPPORTED_LANGUAGES: [&str; 2] = ["en", "tr"];
+const DEFAULT_LANGUAGE: &str = "tr";
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Profile {
+    pub version: u32,
+    pub player_id: String,
+    pub display_name: String,
+    pub color: String,
+    pub language: String,
+}
+
+impl Profile {
+    /// Fresh profile with the same id and name scheme main.ts used.
+    pub fn generate() -> Self {
+        let player_id = format!("player_{}", random_suffix(7));
+        Self {
+            version: PROFILE_VERSION,
+            display_name: player_id.replace("player_", ""),
+            player_id,
+            color: DEFAULT_PLAYER_COLOR.to_string(),
+            language: DEFAULT_LANGUAGE.to_string(),
+        }
+    }
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ProfileUpdate {
+    pub display_name: Option<String>,
+    pub color: Option<String>,
+    pub language: Option<String>,
+}
+
+#[derive(Debug)]
+pub enum ProfileError {
+    InvalidDisplayName(String),
+    InvalidColor(String),
+    UnsupportedLanguage(String),
+    Storage(String),
+}
+
+impl ProfileError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            ProfileError::InvalidDisplayName(_) => "invalid_display_name",
+            ProfileError::InvalidColor(_) => "invalid_color",
+            ProfileError::UnsupportedLanguage(_) => "unsupported_language",
+            ProfileError::Storage(_) => "storage",
+        }
+    }
+}
+
+impl fmt::Display for ProfileError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ProfileError::InvalidDisplayName(reason) => {
+                write!(f, "Invalid display name: {}", reason)
+            }
+            ProfileError::InvalidColor(color) => write!(f, "'{}' is not a player color", color),
+            ProfileError::UnsupportedLanguage(language) => {
+                write!(f, "Language '{}' is not supported", language)
+            }
+            ProfileError::Storage(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for ProfileError {}
+
+impl Serialize for ProfileError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let mut state = serializer.serialize_struct("ProfileError", 2)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.end()
+    }
+}
+
+/// Trims the name and checks it against the length and charset rules.
+/// Letters and digits of any script are allowed, plus space, `_` and `-`.
+pub fn validate_display_name(name: &str) -> Result<String, ProfileError> {
+    let name = name.trim();
+    let length = name.chars().count();
+    if !(DISPLAY_NAME_MIN_LENGTH..=DISPLAY_NAME_MAX_LENGTH).contains(&length) {
+        return Err(ProfileError::InvalidDisplayName(format!(
+            "must be {} to {} characters long",
+            DISPLAY_NAME_MIN_LENGTH, DISPLAY_NAME_MAX_LENGTH
+        )));
+    }
+    if let Some(c) = name
+        .chars()
+        .find(|c| !(c.is_alphanumeric() || *c == ' ' || *c == '_' || *c == '-'))
+    {
+        return Err(ProfileError::InvalidDisplayName(format!(
+            "'{}' is not allowed",
+            c
+        )));
+    }
+    Ok(name.to_string())
+}
+
+pub fn validate_color(color: &str) -> Result<String, ProfileError> {
+    PLAYER_COLORS
+        .iter()
+        .find(|value| value.eq_ignore_ascii_case(color))
+        .map(|value| value.to_string())
+        .ok_or_else(|| ProfileError::InvalidColor(color.to_string()))
+}
+
+pub fn validate_language(language: &str) -> Result<String, ProfileError> {
+    SUPPORTED_LANGUAGES
+        .iter()
+        .find(|code| **code == language)
+        .map(|code| code.to_string())
+        .ok_or_else(|| ProfileError::UnsupportedLanguage(language.to_string()))
+}
+
+/// The local player's profile and the file it lives in.
+pub struct ProfileStore {
+    path: PathBuf,
+    profile: Profile,
+}
+
+impl ProfileStore {
+    /// Loads the profile from `data_dir`, creating and saving a new one on
+    /// first launch.
+    pub fn open(data_dir: &Path) -> Result<Self, ProfileError> {
+        let path = data_dir.join(PROFILE_FILE);
+        if !path.exists() {
+            let store = Self {
+                path,
+                profile: Profile::generate(),
+            };
+            store.save()?;
+            return Ok(store);
+        }
+        let contents = fs::read_to_string(&path).map_err(|e| {
+            ProfileError::Storage(format!("Failed to read {}: {}", path.display(), e))
+        })?;
+        let (profile, migrated) =
+            storage::read_versioned(&contents, PROFILE_VERSION, &PROFILE_MIGRATIONS).map_err(
+                |e| ProfileError::Storage(format!("Player profile could not be read: {}", e)),
+            )?;
+        let store = Self { path, profile };
+        if migrated {
+            store.save()?;
+        }
+        Ok(store)
+    }
+
+    pub fn profile(&self) -> &Profile {
+        &self.profile
+    }
+
+    /// Validates every field before touching the profile, so a bad field
+    /// rejects the whole update.
+    pub fn update(&mut self, update: ProfileUpdate) -> Result<Profile, ProfileError> {
+        let display_name = update
+            .display_name
+            .as_deref()
+            .map(validate_display_name)
+            .transpose()?;
+        let color = update.color.as_deref().map(validate_color).transpose()?;
+        let language = update
+            .language
+            .as_deref()
+            .map(validate_language)
+            .transpose()?;
+
+        let mut profile = self.profile.clone();
+        if let Some(display_name) = display_name {
+            profile.display_name = display_name;
+        }
+        if let Some(color) = color {
+            profile.color = color;
+        }
+        if let Some(language) = language {
+            profile.language = language;
+        }
+        let previous = std::mem::replace(&mut self.profile, profile);
+        if let Err(e) = self.save() {
+            self.profile = previous;
+            return Err(e);
+        }
+        Ok(self.profile.clone())
+    }
+
+    fn save(&self) -> Result<(), ProfileError> {
+        let json = serde_json::to_vec_pretty(&self.profile)
+            .map_err(|e| ProfileError::Storage(format!("Failed to encode profile: {}", e)))?;
+        storage::write_atomic(&self.path, &json).map_err(ProfileError::Storage)
+    }
+}
+
+/// Base36 suffix like `Math.random().toString(36)`, seeded from the
+/// process-random hasher keys so no RNG dependency is needed.
+fn random_suffix(length: usize) -> String {
+    const ALPHABET: &[u8] = b"0123456789abcdefghijklmnopqrstuvwxyz";
+    let mut hasher = RandomState::new().build_hasher();
+    hasher.write_u128(
+        std::time::SystemTime::now()
+            .duration_since(std::time::UNIX_EPOCH)
+            .map(|elapsed| elapsed.as_nanos())
+            .unwrap_or_default(),
+    );
+    let mut value = hasher.finish();
+    (0..length)
+        .map(|_| {
+            let c = ALPHABET[(value % 36) as usize] as char;
+            value /= 36;
+            c
+        })
+        .collect()
+}
+
+#[tauri::command]
+pub fn get_profile(store: State<'_, Mutex<ProfileStore>>) -> Profile {
+    store.lock().unwrap().profile().clone()
+}
+
+#[tauri::command]
+pub fn update_profile(
+    store: State<'_, Mutex<ProfileStore>>,
+    update: ProfileUpdate,
+) -> Result<Profile, ProfileError> {
+    store.lock().unwrap().update(update)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn update(
+        display_name: Option<&str>,
+        color: Option<&str>,
+        language: Option<&str>,
+    ) -> ProfileUpdate {
+        ProfileUpdate {
+            display_name: display_name.map(str::to_string),
+            color: color.map(str::to_string),
+            language: language.map(str::to_string),
+        }
+    }
+
+    #[test]
+    fn display_names_are_trimmed_and_length_checked() {
+        assert_eq!(validate_display_name("  Ada  ").unwrap(), "Ada");
+        assert!(validate_display_name("ab").is_ok());
+        assert!(validate_display_name("a").is_err());
+        assert!(validate_display_name("   a   ").is_err());
+        assert!(validate_display_name(&"x".repeat(DISPLAY_NAME_MAX_LENGTH)).is_ok());
+        assert!(validate_display_name(&"x".repeat(DISPLAY_NAME_MAX_LENGTH + 1)).is_err());
+        // Characters are counted, not bytes.
+        assert!(validate_display_name(&"ğ".repeat(DISPLAY_NAME_MAX_LENGTH)).is_ok());
+    }
+
+    #[test]
+    fn display_names_only_use_allowed_characters() {
+        assert!(validate_display_name("Çağrı_2-b c").is_ok());
+        for name in ["bad!", "a.b", "<script>", "tab\tname"] {
+            assert!(matches!(
+                validate_display_name(name),
+                Err(ProfileError::InvalidDisplayName(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn colors_come_from_the_player_palette() {
+        assert_eq!(validate_color("#3498DB").unwrap(), "#3498db");
+        for color in PLAYER_COLORS {
+            assert_eq!(validate_color(color).unwrap(), color);
+        }
+        assert!(matches!(
+            validate_color("#000000"),
+            Err(ProfileError::InvalidColor(_))
+        ));
+        assert!(validate_color("red").is_err());
+    }
+
+    #[test]
+    fn languages_must_be_supported() {
+        for language in SUPPORTED_LANGUAGES {
+            assert_eq!(validate_language(language).unwrap(), language);
+        }
+        for language in ["de", "EN", ""] {
+            assert!(matches!(
+                validate_language(language),
+                Err(ProfileError::UnsupportedLanguage(_))
+            ));
+        }
+    }
+
+    #[test]
+    fn rejected_updates_leave_the_profile_unchanged() {
+        let dir = storage::test_dir("profile-update");
+        let mut store = ProfileStore::open(&dir).unwrap();
+        let original = store.profile().clone();
+
+        assert!(matches!(
+            store.update(update(Some("Renamed"), Some("#000000"), Some("en"))),
+            Err(ProfileError::InvalidColor(_))
+        ));
+        let reopened = ProfileStore::open(&dir).unwrap();
+        for profile in [store.profile(), reopened.profile()] {
+            assert_eq!(profile.display_name, original.display_name);
+            assert_eq!(profile.color, original.color);
+            assert_eq!(profile.language, original.language);
+        }
+
+        store
+            .update(update(Some("Renamed"), None, Some("en")))
+            .unwrap();
+        let reopened = ProfileStore::open(&dir).unwrap();
+        assert_eq!(reopened.profile().player_id, original.player_id);
+        assert_eq!(reopened.profile().display_name, "Renamed");
+        assert_eq!(reopened.profile().color, original.color);
+        assert_eq!(reopened.profile().language, "en");
+    }
+}
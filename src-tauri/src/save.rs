@@ -0,0 +1,363 @@
+use crate::inventory::{self, Inventory};
+use crate::profile::ProfileStore;
+use crate::storage::{self, Migration, SchemaError};
+use crate::world::{Progress, World};
+use serde::{Deserialize, Serialize};
+use serde_json::Value;
+use std::collections::BTreeMap;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::Mutex;
+use std::time::{SystemTime, UNIX_EPOCH};
+use tauri::{AppHandle, Manager, State};
+use tokio::time::{interval, Duration, MissedTickBehavior};
+
+pub const SAVE_FILE: &str = "save.json";
+pub const SAVE_VERSION: u32 = 3;
+
+/// `SAVE_MIGRATIONS[v]` upgrades a version `v` save to `v + 1`.
+const SAVE_MIGRATIONS: [Migration; SAVE_VERSION as usize] =
+    [storage::stamp_version, add_bank, add_items];
+
+/// Number of previous saves kept as `save.json.1` (newest) to `save.json.N`.
+pub const BACKUP_COUNT: usize = 3;
+
+pub const AUTOSAVE_INTERVAL: Duration = Duration::from_secs(60);
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SaveData {
+    pub version: u32,
+    pub player_id: String,
+    pub inventory: Inventory,
+    pub total_shards: u32,
+    pub gathered: BTreeMap<u8, u32>,
+    pub bank: Inventory,
+    pub items: BTreeMap<String, u32>,
+    /// Unix time in seconds.
+    pub saved_at: u64,
+}
+
+impl SaveData {
+    pub fn progress(&self) -> Progress {
+        Progress {
+            inventory: self.inventory.clone(),
+            gathered: self.gathered.clone(),
+            bank: self.bank.clone(),
+            items: self.items.clone(),
+        }
+    }
+}
+
+fn add_empty_map(document: &mut Value, field: &str) -> Result<(), String> {
+    let object = document
+        .as_object_mut()
+        .ok_or_else(|| "Expected a JSON object".to_string())?;
+    object.insert(field.to_string(), Value::Object(Default::default()));
+    Ok(())
+}
+
+/// Version 2 added the shard bank, which starts out empty.
+fn add_bank(mut document: Value) -> Result<Value, String> {
+    add_empty_map(&mut document, "bank")?;
+    Ok(document)
+}
+
+/// Version 3 added crafted items.
+fn add_items(mut document: Value) -> Result<Value, String> {
+    add_empty_map(&mut document, "items")?;
+    Ok(document)
+}
+
+/// Where saves for the local player are written.
+pub struct SaveStore {
+    dir: PathBuf,
+}
+
+impl SaveStore {
+    pub fn new(dir: &Path) -> Self {
+        Self {
+            dir: dir.to_path_buf(),
+        }
+    }
+
+    fn path(&self) -> PathBuf {
+        self.dir.join(SAVE_FILE)
+    }
+
+    fn backup_path(&self, index: usize) -> PathBuf {
+        self.dir.join(format!("{}.{}", SAVE_FILE, index))
+    }
+
+    /// Shifts the backups down by one, moves the current save into slot 1
+    /// and writes `data` atomically in its place.
+    pub fn write(&self, data: &SaveData) -> Result<(), String> {
+        let json =
+            serde_json::to_vec_pretty(data).map_err(|e| format!("Failed to encode save: {}", e))?;
+        for index in (1..BACKUP_COUNT).rev() {
+            let from = self.backup_path(index);
+            if from.exists() {
+                fs::rename(&from, self.backup_path(index + 1))
+                    .map_err(|e| format!("Failed to rotate {}: {}", from.display(), e))?;
+            }
+        }
+        let path = self.path();
+        if path.exists() {
+            fs::rename(&path, self.backup_path(1))
+                .map_err(|e| format!("Failed to back up {}: {}", path.display(), e))?;
+        }
+        storage::write_atomic(&path, &json)
+    }
+
+    /// Reads the newest readable save, falling back through the backups if
+    /// the main file is missing or corrupted. `None` when nothing was saved.
+    pub fn read(&self) -> Result<Option<SaveData>, String> {
+        let candidates =
+            std::iter::once(self.path()).chain((1..=BACKUP_COUNT).map(|i| self.backup_path(i)));
+        let mut last_error = None;
+        for path in candidates {
+            if !path.exists() {
+                continue;
+            }
+            let contents = match fs::read_to_string(&path) {
+                Ok(contents) => contents,
+                Err(e) => {
+                    println!("Skipping unreadable save {}: {}", path.display(), e);
+                    last_error = Some(format!("{}: {}", path.display(), e));
+                    continue;
+                }
+            };
+            match storage::read_versioned::<SaveData>(&contents, SAVE_VERSION, &SAVE_MIGRATIONS) {
+                Ok((data, _)) => return Ok(Some(data)),
+                // Falling back to an older backup would quietly drop progress.
+                Err(e @ SchemaError::TooNew { .. }) => {
+                    return Err(format!("{}: {}", path.display(), e))
+                }
+                Err(e) => {
+                    println!("Skipping corrupted save {}: {}", path.display(), e);
+                    last_error = Some(format!("{}: {}", path.display(), e));
+                }
+            }
+        }
+        match last_error {
+            Some(e) => Err(format!("No readable save found, last error: {}", e)),
+            None => Ok(None),
+        }
+    }
+}
+
+/// Captures the player's progress from the world.
+pub fn capture(world: &World, player_id: &str) -> Option<SaveData> {
+    let progress = world.progress(player_id)?;
+    let total_shards = inventory::total_shards(&progress.inventory);
+    let saved_at = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|elapsed| elapsed.as_secs())
+        .unwrap_or_default();
+    Some(SaveData {
+        version: SAVE_VERSION,
+        player_id: player_id.to_string(),
+        inventory: progress.inventory,
+        total_shards,
+        gathered: progress.gathered,
+        bank: progress.bank,
+        items: progress.items,
+        saved_at,
+    })
+}
+
+/// Saves the local player. Does nothing before startup has created the
+/// profile and save stores.
+pub fn save_local(app: &AppHandle) -> Result<Option<SaveData>, String> {
+    let (Some(profiles), Some(saves)) = (
+        app.try_state::<Mutex<ProfileStore>>(),
+        app.try_state::<SaveStore>(),
+    ) else {
+        return Ok(None);
+    };
+    let player_id = profiles.lock().unwrap().profile().player_id.clone();
+    let data = capture(&app.state::<Mutex<World>>().lock().unwrap(), &player_id);
+    let Some(data) = data else {
+        return Ok(None);
+    };
+    saves.write(&data)?;
+    Ok(Some(data))
+}
+
+pub async fn run_autosave(app: AppHandle) {
+    let mut ticker = interval(AUTOSAVE_INTERVAL);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Delay);
+    // The first tick completes immediately, skip it.
+    ticker.tick().await;
+    loop {
+        ticker.tick().await;
+        if let Err(e) = save_local(&app) {
+            println!("Autosave failed: {}", e);
+        }
+    }
+}
+
+#[tauri::command]
+pub fn save_game(app: AppHandle) -> Result<SaveData, String> {
+    save_local(&app)?.ok_or_else(|| "Local player has not joined the world yet".to_string())
+}
+
+/// Reloads the last save into the world for the local player.
+#[tauri::command]
+pub fn load_game(
+    saves: State<'_, SaveStore>,
+    profiles: State<'_, Mutex<ProfileStore>>,
+    world: State<'_, Mutex<World>>,
+) -> Result<Option<SaveData>, String> {
+    let Some(data) = saves.read()? else {
+        return Ok(None);
+    };
+    let player_id = profiles.lock().unwrap().profile().player_id.clone();
+    if data.player_id != player_id {
+        return Err(format!(
+            "Save belongs to '{}', not to '{}'",
+            data.player_id, player_id
+        ));
+    }
+    world
+        .lock()
+        .unwrap()
+        .restore_progress(&player_id, data.progress())
+        .map_err(|e| e.to_string())?;
+    Ok(Some(data))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn read(contents: &str) -> Result<(SaveData, bool), SchemaError> {
+        storage::read_versioned(contents, SAVE_VERSION, &SAVE_MIGRATIONS)
+    }
+
+    #[test]
+    fn unversioned_saves_are_migrated() {
+        let (data, migrated) = read(
+            r#"{"playerId":"a","inventory":{"0":3},"totalShards":30,"gathered":{"0":5},"savedAt":1}"#,
+        )
+        .unwrap();
+        assert!(migrated);
+        assert_eq!(data.version, SAVE_VERSION);
+        assert_eq!(data.inventory.get(&0), Some(&3));
+        assert!(data.bank.is_empty());
+        assert!(data.items.is_empty());
+    }
+
+    #[test]
+    fn migrations_keep_existing_fields() {
+        let (data, migrated) = read(
+            r#"{"version":2,"playerId":"a","inventory":{},"totalShards":0,"gathered":{},"bank":{"1":4},"savedAt":1}"#,
+        )
+        .unwrap();
+        assert!(migrated);
+        assert_eq!(data.bank.get(&1), Some(&4));
+        assert!(data.items.is_empty());
+    }
+
+    #[test]
+    fn current_saves_are_read_as_is() {
+        let (_, migrated) = read(
+            r#"{"version":3,"playerId":"a","inventory":{},"totalShards":0,"gathered":{},"bank":{},"items":{"pickaxe":1},"savedAt":1}"#,
+        )
+        .unwrap();
+        assert!(!migrated);
+    }
+
+    #[test]
+    fn newer_saves_are_refused() {
+        assert!(matches!(
+            read(r#"{"version":4,"playerId":"a"}"#),
+            Err(SchemaError::TooNew {
+                found: 4,
+                supported: SAVE_VERSION
+            })
+        ));
+        assert!(matches!(read("[]"), Err(SchemaError::Invalid(_))));
+    }
+
+    fn data(saved_at: u64) -> SaveData {
+        SaveData {
+            version: SAVE_VERSION,
+            player_id: "a".to_string(),
+            inventory: Inventory::default(),
+            total_shards: 0,
+            gathered: BTreeMap::new(),
+            bank: Inventory::default(),
+            items: BTreeMap::new(),
+            saved_at,
+        }
+    }
+
+    fn saved_at(path: &Path) -> u64 {
+        let contents = fs::read_to_string(path).unwrap();
+        serde_json::from_str::<SaveData>(&contents)
+            .unwrap()
+            .saved_at
+    }
+
+    #[test]
+    fn nothing_saved_reads_as_none() {
+        let store = SaveStore::new(&storage::test_dir("save-empty"));
+        assert!(store.read().unwrap().is_none());
+    }
+
+    #[test]
+    fn writes_rotate_the_backups() {
+        let store = SaveStore::new(&storage::test_dir("save-rotate"));
+        for time in 1..=5 {
+            store.write(&data(time)).unwrap();
+        }
+        assert_eq!(saved_at(&store.path()), 5);
+        for index in 1..=BACKUP_COUNT {
+            assert_eq!(saved_at(&store.backup_path(index)), 5 - index as u64);
+        }
+        assert!(!store.backup_path(BACKUP_COUNT + 1).exists());
+        assert_eq!(store.read().unwrap().unwrap().saved_at, 5);
+    }
+
+    #[test]
+    fn writes_go_through_a_temp_file() {
+        let dir = storage::test_dir("save-atomic");
+        let store = SaveStore::new(&dir);
+        store.write(&data(1)).unwrap();
+        let temp_path = store.path().with_extension("tmp");
+        assert!(!temp_path.exists());
+
+        // A crash mid-write leaves a partial temp file and the old save.
+        fs::write(&temp_path, "{\"version\":3,\"play").unwrap();
+        assert_eq!(store.read().unwrap().unwrap().saved_at, 1);
+        store.write(&data(2)).unwrap();
+        assert!(!temp_path.exists());
+        assert_eq!(store.read().unwrap().unwrap().saved_at, 2);
+    }
+
+    #[test]
+    fn corrupted_saves_fall_back_to_the_newest_backup() {
+        let store = SaveStore::new(&storage::test_dir("save-fallback"));
+        for time in 1..=3 {
+            store.write(&data(time)).unwrap();
+        }
+        fs::write(store.path(), "{ not json").unwrap();
+        assert_eq!(store.read().unwrap().unwrap().saved_at, 2);
+
+        fs::write(store.backup_path(1), "").unwrap();
+        assert_eq!(store.read().unwrap().unwrap().saved_at, 1);
+
+        fs::write(store.backup_path(2), "[]").unwrap();
+        assert!(store.read().is_err());
+    }
+
+    #[test]
+    fn newer_saves_are_not_skipped() {
+        let store = SaveStore::new(&storage::test_dir("save-newer"));
+        store.write(&data(1)).unwrap();
+        store.write(&data(2)).unwrap();
+        fs::write(store.path(), r#"{"version":99,"playerId":"a"}"#).unwrap();
+        assert!(store.read().is_err());
+    }
+}
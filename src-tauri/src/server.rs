@@ -0,0 +1,78 @@
+use std::net::{IpAddr, Ipv4Addr, SocketAddr};
+use std::path::PathBuf;
+use the_gatherer_lib::discovery;
+use the_gatherer_lib::network::{self, ServerConfig, DEFAULT_SERVER_PORT};
+
+const USAGE: &str = "Usage: the-gatherer-server [--bind <address>] [--port <port>] [--map <file> | --seed <seed>] [--name <name>] [--announce <address> | --no-announce]";
+
+const DEFAULT_SESSION_NAME: &str = "LAN session";
+
+fn parse_args(mut args: impl Iterator<Item = String>) -> Result<ServerConfig, String> {
+    let mut address = IpAddr::V4(Ipv4Addr::UNSPECIFIED);
+    let mut port = DEFAULT_SERVER_PORT;
+    let mut map_path = None;
+    let mut seed = None;
+    let mut name = DEFAULT_SESSION_NAME.to_string();
+    let mut announce = Some(discovery::broadcast_address());
+
+    while let Some(arg) = args.next() {
+        let mut value = || {
+            args.next()
+                .ok_or_else(|| format!("Missing value for {}", arg))
+        };
+        match arg.as_str() {
+            "--bind" => {
+                address = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid bind address: {}", e))?
+            }
+            "--port" => {
+                port = value()?
+                    .parse()
+                    .map_err(|e| format!("Invalid port: {}", e))?
+            }
+            "--map" => map_path = Some(PathBuf::from(value()?)),
+            "--seed" => {
+                seed = Some(
+                    value()?
+                        .parse()
+                        .map_err(|e| format!("Invalid seed: {}", e))?,
+                )
+            }
+            "--name" => name = value()?,
+            "--announce" => {
+                announce = Some(
+                    value()?
+                        .parse()
+                        .map_err(|e| format!("Invalid announce address: {}", e))?,
+                )
+            }
+            "--no-announce" => announce = None,
+            _ => return Err(format!("Unknown argument '{}'", arg)),
+        }
+    }
+
+    Ok(ServerConfig {
+        bind: SocketAddr::new(address, port),
+        map_path,
+        seed,
+        name,
+        announce,
+    })
+}
+
+#[tokio::main]
+async fn main() {
+    let config = match parse_args(std::env::args().skip(1)) {
+        Ok(config) => config,
+        Err(e) => {
+            eprintln!("{}\n{}", e, USAGE);
+            std::process::exit(2);
+        }
+    };
+    println!("Dedicated server listening on {}", config.bind);
+    if let Err(e) = network::run_server(config).await {
+        eprintln!("Server stopped: {}", e);
+        std::process::exit(1);
+    }
+}
@@ -0,0 +1,390 @@
+use crate::vector2::Vector2;
+use std::collections::HashMap;
+use std::hash::Hash;
+
+/// Cell size of a default grid. A viewport spans a handful of cells.
+const DEFAULT_CELL_SIZE: f64 = 256.0;
+
+/// Uniform grid bucketing entries by position, so proximity queries only
+/// visit the cells they overlap instead of every entry.
+pub struct SpatialGrid<K> {
+    cell_size: f64,
+    cells: HashMap<(i64, i64), Vec<(K, Vector2)>>,
+    positions: HashMap<K, Vector2>,
+    /// Lowest and highest cell ever occupied since the last `clear`. Queries
+    /// are clamped to it, so far-off points never walk empty cells.
+    bounds: Option<((i64, i64), (i64, i64))>,
+}
+
+impl<K: Clone + Eq + Hash> Default for SpatialGrid<K> {
+    fn default() -> Self {
+        Self::new(DEFAULT_CELL_SIZE)
+    }
+}
+
+impl<K: Clone + Eq + Hash> SpatialGrid<K> {
+    pub fn new(cell_size: f64) -> Self {
+        Self {
+            cell_size,
+            cells: HashMap::new(),
+            positions: HashMap::new(),
+            bounds: None,
+        }
+    }
+
+    /// Casts saturate, so points beyond `i64` cells land in the outermost one.
+    fn cell_of(&self, position: Vector2) -> (i64, i64) {
+        (
+            (position.x / self.cell_size).floor() as i64,
+            (position.y / self.cell_size).floor() as i64,
+        )
+    }
+
+    pub fn clear(&mut self) {
+        self.cells.clear();
+        self.positions.clear();
+        self.bounds = None;
+    }
+
+    /// Adds `key` at `position`, moving it if it is already indexed.
+    pub fn insert(&mut self, key: K, position: Vector2) {
+        let cell = self.cell_of(position);
+        if let Some(previous) = self.positions.get(&key).copied() {
+            if self.cell_of(previous) == cell {
+                if let Some(entry) = self
+                    .cells
+                    .get_mut(&cell)
+                    .and_then(|entries| entries.iter_mut().find(|(other, _)| *other == key))
+                {
+                    entry.1 = position;
+                }
+                self.positions.insert(key, position);
+                return;
+            }
+            self.remove(&key);
+        }
+        self.cells
+            .entry(cell)
+            .or_default()
+            .push((key.clone(), position));
+        self.positions.insert(key, position);
+        self.bounds = Some(match self.bounds {
+            Some((low, high)) => (
+                (low.0.min(cell.0), low.1.min(cell.1)),
+                (high.0.max(cell.0), high.1.max(cell.1)),
+            ),
+            None => (cell, cell),
+        });
+    }
+
+    pub fn remove(&mut self, key: &K) {
+        let Some(position) = self.positions.remove(key) else {
+            return;
+        };
+        let cell = self.cell_of(position);
+        if let Some(entries) = self.cells.get_mut(&cell) {
+            entries.retain(|(other, _)| other != key);
+            if entries.is_empty() {
+                self.cells.remove(&cell);
+            }
+        }
+        if self.cells.is_empty() {
+            self.bounds = None;
+        }
+    }
+
+    /// Entries inside the box from `min` to `max`, edges included.
+    pub fn query_rect(&self, min: Vector2, max: Vector2) -> Vec<&K> {
+        let mut found = Vec::new();
+        let Some((low, high)) = self.bounds else {
+            return found;
+        };
+        let (min_x, min_y) = self.cell_of(min);
+        let (max_x, max_y) = self.cell_of(max);
+        let (min_x, min_y) = (min_x.max(low.0), min_y.max(low.1));
+        let (max_x, max_y) = (max_x.min(high.0), max_y.min(high.1));
+        if min_x > max_x || min_y > max_y {
+            return found;
+        }
+        // Sparse grids are cheaper to scan by occupied cell than by range.
+        let span = (max_x.abs_diff(min_x).saturating_add(1))
+            .saturating_mul(max_y.abs_diff(min_y).saturating_add(1));
+        if span > self.cells.len() as u64 {
+            for (&(x, y), entries) in &self.cells {
+                if (min_x..=max_x).contains(&x) && (min_y..=max_y).contains(&y) {
+                    collect_in_rect(entries, min, max, &mut found);
+                }
+            }
+            return found;
+        }
+        for y in min_y..=max_y {
+            for x in min_x..=max_x {
+                if let Some(entries) = self.cells.get(&(x, y)) {
+                    collect_in_rect(entries, min, max, &mut found);
+                }
+            }
+        }
+        found
+    }
+
+    /// Entries within `radius` of `center`, nearest first.
+    pub fn query_radius(&self, center: Vector2, radius: f64) -> Vec<(&K, f64)> {
+        let corner = Vector2::new(radius, radius);
+        let mut found: Vec<(&K, f64)> = self
+            .query_rect(center - corner, center + corner)
+            .into_iter()
+            .filter_map(|key| {
+                let distance = self.positions[key].distance_to(center);
+                (distance <= radius).then_some((key, distance))
+            })
+            .collect();
+        found.sort_by(|a, b| a.1.total_cmp(&b.1));
+        found
+    }
+
+    /// The entry closest to `center` accepted by `filter`. Searches rings of
+    /// cells outwards from the first one that can hold an entry, and stops
+    /// once no closer entry can exist.
+    pub fn nearest(&self, center: Vector2, filter: impl Fn(&K) -> bool) -> Option<(&K, f64)> {
+        let ((low_x, low_y), (high_x, high_y)) = self.bounds?;
+        let (center_x, center_y) = self.cell_of(center);
+        let gap = |center: i64, low: i64, high: i64| {
+            if center < low {
+                low.abs_diff(center)
+            } else if center > high {
+                center.abs_diff(high)
+            } else {
+                0
+            }
+        };
+        let first_ring = gap(center_x, low_x, high_x).max(gap(center_y, low_y, high_y));
+        // No occupied cell is further away than this many rings.
+        let last_ring = center_x
+            .abs_diff(low_x)
+            .max(center_x.abs_diff(high_x))
+            .max(center_y.abs_diff(low_y))
+            .max(center_y.abs_diff(high_y));
+
+        let mut best: Option<(&K, f64)> = None;
+        let mut visited: u64 = 0;
+        for ring in first_ring..=last_ring {
+            // Anything in this ring or beyond is at least this far away.
+            let ring_distance = ring.saturating_sub(1) as f64 * self.cell_size;
+            if best.is_some_and(|(_, distance)| distance < ring_distance) {
+                break;
+            }
+            // Wide rings around few entries cost more than checking them all.
+            visited = visited.saturating_add(ring.saturating_mul(8).max(1));
+            if visited > self.cells.len() as u64 {
+                return self.nearest_by_scan(center, filter);
+            }
+            for (x, y) in ring_cells(center_x, center_y, ring as i64) {
+                let Some(entries) = self.cells.get(&(x, y)) else {
+                    continue;
+                };
+                for (key, position) in entries {
+                    if !filter(key) {
+                        continue;
+                    }
+                    let distance = position.distance_to(center);
+                    if best.is_none_or(|(_, best_distance)| distance < best_distance) {
+                        best = Some((key, distance));
+                    }
+                }
+            }
+        }
+        best
+    }
+
+    fn nearest_by_scan(&self, center: Vector2, filter: impl Fn(&K) -> bool) -> Option<(&K, f64)> {
+        self.positions
+            .iter()
+            .filter(|(key, _)| filter(key))
+            .map(|(key, position)| (key, position.distance_to(center)))
+            .min_by(|a, b| a.1.total_cmp(&b.1))
+    }
+}
+
+fn collect_in_rect<'a, K>(
+    entries: &'a [(K, Vector2)],
+    min: Vector2,
+    max: Vector2,
+    found: &mut Vec<&'a K>,
+) {
+    found.extend(
+        entries
+            .iter()
+            .filter(|(_, p)| p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y)
+            .map(|(key, _)| key),
+    );
+}
+
+/// Cells on the square ring `ring` cells away from the center cell.
+fn ring_cells(center_x: i64, center_y: i64, ring: i64) -> Vec<(i64, i64)> {
+    if ring == 0 {
+        return vec![(center_x, center_y)];
+    }
+    // Saturating, cells past the edge of `i64` simply repeat the last one.
+    let at = |center: i64, offset: i64| center.saturating_add(offset);
+    let mut cells = Vec::with_capacity(ring as usize * 8);
+    for offset in -ring..=ring {
+        cells.push((at(center_x, offset), at(center_y, -ring)));
+        cells.push((at(center_x, offset), at(center_y, ring)));
+    }
+    for offset in (1 - ring)..ring {
+        cells.push((at(center_x, -ring), at(center_y, offset)));
+        cells.push((at(center_x, ring), at(center_y, offset)));
+    }
+    cells
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::generator::SeededRng;
+
+    /// A grid and the same entries as a list to check it against.
+    fn scattered(count: usize, seed: u64) -> (SpatialGrid<usize>, Vec<Vector2>) {
+        let mut rng = SeededRng::new(seed);
+        let mut grid = SpatialGrid::new(100.0);
+        let positions: Vec<Vector2> = (0..count)
+            .map(|_| Vector2::new(rng.range(-500.0, 3500.0), rng.range(-500.0, 3500.0)))
+            .collect();
+        for (key, position) in positions.iter().enumerate() {
+            grid.insert(key, *position);
+        }
+        (grid, positions)
+    }
+
+    fn sorted(keys: Vec<&usize>) -> Vec<usize> {
+        let mut keys: Vec<usize> = keys.into_iter().copied().collect();
+        keys.sort_unstable();
+        keys
+    }
+
+    fn brute_nearest(
+        positions: &[Vector2],
+        center: Vector2,
+        filter: impl Fn(&usize) -> bool,
+    ) -> Option<f64> {
+        (0..positions.len())
+            .filter(|key| filter(key))
+            .map(|key| positions[key].distance_to(center))
+            .min_by(|a, b| a.total_cmp(b))
+    }
+
+    #[test]
+    fn query_rect_matches_a_full_scan() {
+        let (grid, positions) = scattered(2000, 1);
+        let mut rng = SeededRng::new(2);
+        for _ in 0..200 {
+            let a = Vector2::new(rng.range(-1000.0, 4000.0), rng.range(-1000.0, 4000.0));
+            let size = Vector2::new(rng.range(0.0, 1500.0), rng.range(0.0, 1500.0));
+            let (min, max) = (a, a + size);
+            let expected: Vec<usize> = (0..positions.len())
+                .filter(|&key| {
+                    let p = positions[key];
+                    p.x >= min.x && p.x <= max.x && p.y >= min.y && p.y <= max.y
+                })
+                .collect();
+            assert_eq!(sorted(grid.query_rect(min, max)), expected);
+        }
+    }
+
+    #[test]
+    fn query_radius_matches_a_full_scan() {
+        let (grid, positions) = scattered(2000, 3);
+        let mut rng = SeededRng::new(4);
+        for _ in 0..200 {
+            let center = Vector2::new(rng.range(-1000.0, 4000.0), rng.range(-1000.0, 4000.0));
+            let radius = rng.range(0.0, 800.0);
+            let found = grid.query_radius(center, radius);
+            assert!(found.windows(2).all(|pair| pair[0].1 <= pair[1].1));
+            let expected: Vec<usize> = (0..positions.len())
+                .filter(|&key| positions[key].distance_to(center) <= radius)
+                .collect();
+            let keys = found.into_iter().map(|(key, _)| key).collect();
+            assert_eq!(sorted(keys), expected);
+        }
+    }
+
+    #[test]
+    fn nearest_matches_a_full_scan() {
+        let (grid, positions) = scattered(2000, 5);
+        let mut rng = SeededRng::new(6);
+        for round in 0..300usize {
+            // Far-off centers take the scan path, the rest the ring search.
+            let spread = if round.is_multiple_of(10) {
+                1e7
+            } else {
+                4000.0
+            };
+            let center = Vector2::new(rng.range(-spread, spread), rng.range(-spread, spread));
+            // Rare matches push the search through many rings before the
+            // early exit may stop it.
+            let modulus = [1, 7, 97, 1999][round % 4];
+            let filter = |key: &usize| key.is_multiple_of(modulus);
+            let found = grid.nearest(center, filter).map(|(_, distance)| distance);
+            assert_eq!(found, brute_nearest(&positions, center, filter));
+        }
+        assert!(grid.nearest(Vector2::new(0.0, 0.0), |_| false).is_none());
+    }
+
+    #[test]
+    fn extreme_queries_do_not_overflow() {
+        let (grid, positions) = scattered(500, 7);
+        let everything = sorted(grid.query_rect(
+            Vector2::new(f64::MIN, f64::MIN),
+            Vector2::new(f64::MAX, f64::MAX),
+        ));
+        assert_eq!(everything.len(), positions.len());
+        assert_eq!(
+            grid.query_radius(Vector2::new(0.0, 0.0), f64::INFINITY)
+                .len(),
+            500
+        );
+        assert!(grid
+            .query_rect(Vector2::new(1e30, 1e30), Vector2::new(f64::MAX, f64::MAX))
+            .is_empty());
+
+        for center in [
+            Vector2::new(f64::MAX, f64::MIN),
+            Vector2::new(-1e300, 1e300),
+            Vector2::new(f64::INFINITY, 0.0),
+        ] {
+            assert!(grid.nearest(center, |_| true).is_some());
+        }
+        let far = Vector2::new(1e15, -1e15);
+        assert_eq!(
+            grid.nearest(far, |_| true).map(|(_, distance)| distance),
+            brute_nearest(&positions, far, |_| true)
+        );
+    }
+
+    #[test]
+    fn moved_and_removed_entries_are_found_where_they_are() {
+        let mut grid = SpatialGrid::new(100.0);
+        grid.insert("a", Vector2::new(50.0, 50.0));
+        grid.insert("b", Vector2::new(1050.0, 50.0));
+        grid.insert("a", Vector2::new(2050.0, 50.0));
+        assert!(grid.query_radius(Vector2::new(50.0, 50.0), 10.0).is_empty());
+        assert_eq!(
+            grid.nearest(Vector2::new(2000.0, 50.0), |_| true)
+                .unwrap()
+                .0,
+            &"a"
+        );
+        grid.remove(&"a");
+        assert_eq!(
+            grid.nearest(Vector2::new(2000.0, 50.0), |_| true)
+                .unwrap()
+                .0,
+            &"b"
+        );
+        grid.remove(&"b");
+        assert!(grid.nearest(Vector2::new(0.0, 0.0), |_| true).is_none());
+        assert!(grid
+            .query_rect(Vector2::new(0.0, 0.0), Vector2::new(3000.0, 3000.0))
+            .is_empty());
+    }
+}
@@ -0,0 +1,331 @@
+use serde::Serialize;
+use std::collections::HashSet;
+use std::time::{Duration, Instant};
+
+pub const STARTUP_PROGRESS_EVENT: &str = "startup-progress";
+pub const STARTUP_RETRY_EVENT: &str = "startup-retry";
+
+pub const DEFAULT_STARTUP_TIMEOUT: Duration = Duration::from_secs(30);
+
+pub const ERROR_TASK_FAILED: &str = "task_failed";
+pub const ERROR_TIMEOUT: &str = "timeout";
+
+#[derive(Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TaskStatus {
+    Pending,
+    Running,
+    Complete,
+    Failed,
+}
+
+pub struct StartupTask {
+    pub name: String,
+    pub depends_on: Vec<String>,
+    pub required: bool,
+    pub status: TaskStatus,
+    pub percent: u8,
+    pub step: Option<String>,
+    pub failure: Option<StartupFailure>,
+}
+
+impl StartupTask {
+    pub fn progress(&self) -> TaskProgress {
+        TaskProgress {
+            task: self.name.clone(),
+            status: self.status,
+            percent: self.percent,
+            step: self.step.clone(),
+            failure: self.failure.clone(),
+        }
+    }
+}
+
+#[derive(Clone, Serialize)]
+pub struct StartupFailure {
+    pub code: String,
+    pub message: String,
+}
+
+/// Payload of the `startup-progress` event sent to the splashscreen.
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TaskProgress {
+    pub task: String,
+    pub status: TaskStatus,
+    pub percent: u8,
+    pub step: Option<String>,
+    pub failure: Option<StartupFailure>,
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct StartupStatus {
+    pub finished: bool,
+    pub percent: u8,
+    pub tasks: Vec<TaskProgress>,
+}
+
+pub struct StartupRegistry {
+    tasks: Vec<StartupTask>,
+    timeout: Duration,
+    started_at: Instant,
+}
+
+impl StartupRegistry {
+    pub fn new(timeout: Duration) -> Self {
+        Self {
+            tasks: Vec::new(),
+            timeout,
+            started_at: Instant::now(),
+        }
+    }
+
+    pub fn register(
+        &mut self,
+        name: &str,
+        depends_on: Vec<String>,
+        required: bool,
+    ) -> Result<(), String> {
+        if name.is_empty() {
+            return Err("Task name cannot be empty".to_string());
+        }
+        if self.get(name).is_some() {
+            return Err(format!("Task '{}' is already registered", name));
+        }
+        if depends_on.iter().any(|dep| dep == name) {
+            return Err(format!("Task '{}' cannot depend on itself", name));
+        }
+        // Dependencies may be registered later, but a cycle through the
+        // tasks already known could never complete.
+        if let Some(dep) = depends_on.iter().find(|dep| self.reaches(dep, name)) {
+            return Err(format!(
+                "Task '{}' cannot depend on '{}', which depends on it",
+                name, dep
+            ));
+        }
+        self.tasks.push(StartupTask {
+            name: name.to_string(),
+            depends_on,
+            required,
+            status: TaskStatus::Pending,
+            percent: 0,
+            step: None,
+            failure: None,
+        });
+        Ok(())
+    }
+
+    /// Whether `from` depends on `to`, directly or through other tasks.
+    fn reaches(&self, from: &str, to: &str) -> bool {
+        let mut seen = HashSet::new();
+        let mut stack = vec![from];
+        while let Some(current) = stack.pop() {
+            if current == to {
+                return true;
+            }
+            if !seen.insert(current) {
+                continue;
+            }
+            if let Some(task) = self.get(current) {
+                stack.extend(task.depends_on.iter().map(String::as_str));
+            }
+        }
+        false
+    }
+
+    pub fn get(&self, name: &str) -> Option<&StartupTask> {
+        self.tasks.iter().find(|task| task.name == name)
+    }
+
+    fn get_mut(&mut self, name: &str) -> Result<&mut StartupTask, String> {
+        self.tasks
+            .iter_mut()
+            .find(|task| task.name == name)
+            .ok_or_else(|| format!("Unknown startup task '{}'", name))
+    }
+
+    /// Records progress for a running task. Percent is clamped to 99 so that
+    /// only `complete` can report a task as done.
+    pub fn update(
+        &mut self,
+        name: &str,
+        percent: u8,
+        step: Option<String>,
+    ) -> Result<TaskProgress, String> {
+        let task = self.get_mut(name)?;
+        if task.status == TaskStatus::Complete {
+            return Err(format!("Task '{}' has already completed", name));
+        }
+        task.status = TaskStatus::Running;
+        task.percent = percent.min(99);
+        task.step = step;
+        task.failure = None;
+        Ok(task.progress())
+    }
+
+    /// Marks a task complete. Every dependency has to be registered and
+    /// already complete, otherwise the call is rejected.
+    pub fn complete(&mut self, name: &str) -> Result<TaskProgress, String> {
+        let task = self
+            .get(name)
+            .ok_or_else(|| format!("Unknown startup task '{}'", name))?;
+        for dep in &task.depends_on {
+            match self.get(dep) {
+                Some(dep_task) if dep_task.status == TaskStatus::Complete => {}
+                Some(_) => return Err(format!("Task '{}' cannot complete before '{}'", name, dep)),
+                None => return Err(format!("Task '{}' depends on unknown task '{}'", name, dep)),
+            }
+        }
+        let task = self.get_mut(name)?;
+        task.status = TaskStatus::Complete;
+        task.percent = 100;
+        task.failure = None;
+        Ok(task.progress())
+    }
+
+    pub fn fail(
+        &mut self,
+        name: &str,
+        code: &str,
+        message: String,
+    ) -> Result<TaskProgress, String> {
+        let task = self.get_mut(name)?;
+        if task.status == TaskStatus::Complete {
+            return Err(format!("Task '{}' has already completed", name));
+        }
+        task.status = TaskStatus::Failed;
+        task.failure = Some(StartupFailure {
+            code: code.to_string(),
+            message,
+        });
+        Ok(task.progress())
+    }
+
+    /// Puts a failed task back to pending and restarts the overall timeout.
+    pub fn retry(&mut self, name: &str) -> Result<TaskProgress, String> {
+        let task = self.get_mut(name)?;
+        if task.status != TaskStatus::Failed {
+            return Err(format!("Task '{}' has not failed", name));
+        }
+        task.status = TaskStatus::Pending;
+        task.percent = 0;
+        task.step = None;
+        task.failure = None;
+        let progress = task.progress();
+        self.started_at = Instant::now();
+        Ok(progress)
+    }
+
+    /// Fails every unfinished required task once the timeout has elapsed and
+    /// returns the tasks that changed.
+    pub fn check_timeout(&mut self) -> Vec<TaskProgress> {
+        if self.started_at.elapsed() < self.timeout {
+            return Vec::new();
+        }
+        let message = format!(
+            "Startup did not finish within {} seconds",
+            self.timeout.as_secs()
+        );
+        self.tasks
+            .iter_mut()
+            .filter(|task| {
+                task.required && matches!(task.status, TaskStatus::Pending | TaskStatus::Running)
+            })
+            .map(|task| {
+                task.status = TaskStatus::Failed;
+                task.failure = Some(StartupFailure {
+                    code: ERROR_TIMEOUT.to_string(),
+                    message: message.clone(),
+                });
+                task.progress()
+            })
+            .collect()
+    }
+
+    /// True once every required task has completed.
+    pub fn is_finished(&self) -> bool {
+        self.tasks
+            .iter()
+            .filter(|task| task.required)
+            .all(|task| task.status == TaskStatus::Complete)
+    }
+
+    /// Snapshot used by a (re)loaded splashscreen to catch up. The overall
+    /// percentage is the mean over required tasks.
+    pub fn status(&self) -> StartupStatus {
+        let required: Vec<&StartupTask> = self.tasks.iter().filter(|task| task.required).collect();
+        let percent = if required.is_empty() {
+            100
+        } else {
+            let total: u32 = required.iter().map(|task| task.percent as u32).sum();
+            (total / required.len() as u32) as u8
+        };
+        StartupStatus {
+            finished: self.is_finished(),
+            percent,
+            tasks: self.tasks.iter().map(StartupTask::progress).collect(),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn deps(names: &[&str]) -> Vec<String> {
+        names.iter().map(|name| name.to_string()).collect()
+    }
+
+    #[test]
+    fn register_rejects_cycles() {
+        let mut registry = StartupRegistry::new(DEFAULT_STARTUP_TIMEOUT);
+        registry.register("a", deps(&["b"]), true).unwrap();
+        registry.register("b", deps(&["c"]), true).unwrap();
+        assert!(registry.register("c", deps(&["a"]), true).is_err());
+        assert!(registry.get("c").is_none());
+        assert!(registry.register("c", Vec::new(), true).is_ok());
+    }
+
+    #[test]
+    fn register_rejects_self_dependency() {
+        let mut registry = StartupRegistry::new(DEFAULT_STARTUP_TIMEOUT);
+        assert!(registry.register("a", deps(&["a"]), true).is_err());
+    }
+
+    #[test]
+    fn dependencies_may_be_registered_later() {
+        let mut registry = StartupRegistry::new(DEFAULT_STARTUP_TIMEOUT);
+        registry
+            .register("assets", deps(&["backend"]), true)
+            .unwrap();
+        assert!(registry.complete("assets").is_err());
+        registry.register("backend", Vec::new(), true).unwrap();
+        registry.complete("backend").unwrap();
+        registry.complete("assets").unwrap();
+        assert!(registry.is_finished());
+    }
+
+    #[test]
+    fn optional_tasks_do_not_hold_up_startup() {
+        let mut registry = StartupRegistry::new(DEFAULT_STARTUP_TIMEOUT);
+        registry.register("backend", Vec::new(), true).unwrap();
+        registry.register("music", Vec::new(), false).unwrap();
+        registry.complete("backend").unwrap();
+        assert!(registry.is_finished());
+        registry.complete("music").unwrap();
+        assert!(registry.is_finished());
+    }
+
+    #[test]
+    fn timeout_fails_unfinished_required_tasks() {
+        let mut registry = StartupRegistry::new(Duration::ZERO);
+        registry.register("backend", Vec::new(), true).unwrap();
+        registry.register("music", Vec::new(), false).unwrap();
+        let failed = registry.check_timeout();
+        assert_eq!(failed.len(), 1);
+        assert_eq!(failed[0].task, "backend");
+        assert!(registry.retry("backend").is_ok());
+        assert!(registry.retry("backend").is_err());
+    }
+}
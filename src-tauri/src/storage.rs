@@ -0,0 +1,108 @@
+use serde::de::DeserializeOwned;
+use serde_json::Value;
+use std::fmt;
+use std::fs;
+use std::io::Write;
+use std::path::Path;
+#[cfg(test)]
+use std::path::PathBuf;
+
+/// Upgrades a document by one schema version.
+pub type Migration = fn(Value) -> Result<Value, String>;
+
+/// Writes `contents` to a sibling temp file and renames it over `path`, so a
+/// crash mid-write leaves either the old file or the new one, never half of
+/// each.
+pub fn write_atomic(path: &Path, contents: &[u8]) -> Result<(), String> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)
+            .map_err(|e| format!("Failed to create {}: {}", dir.display(), e))?;
+    }
+    let temp_path = path.with_extension("tmp");
+    let mut file = fs::File::create(&temp_path)
+        .map_err(|e| format!("Failed to create {}: {}", temp_path.display(), e))?;
+    file.write_all(contents)
+        .and_then(|_| file.sync_all())
+        .map_err(|e| format!("Failed to write {}: {}", temp_path.display(), e))?;
+    fs::rename(&temp_path, path).map_err(|e| format!("Failed to replace {}: {}", path.display(), e))
+}
+
+#[derive(Debug)]
+pub enum SchemaError {
+    /// Written by a newer build of the game than this one.
+    TooNew {
+        found: u64,
+        supported: u32,
+    },
+    Invalid(String),
+}
+
+impl fmt::Display for SchemaError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SchemaError::TooNew { found, supported } => write!(
+                f,
+                "Schema version {} is newer than the supported version {}, update the game to read it",
+                found, supported
+            ),
+            SchemaError::Invalid(e) => write!(f, "{}", e),
+        }
+    }
+}
+
+impl std::error::Error for SchemaError {}
+
+/// Parses a versioned JSON document, running `migrations[v]` to go from
+/// version `v` to `v + 1` until it reaches `current`. Documents without a
+/// `version` field are treated as version 0. Returns the value and whether
+/// it was migrated, so callers can write the upgraded file back.
+pub fn read_versioned<T: DeserializeOwned>(
+    contents: &str,
+    current: u32,
+    migrations: &[Migration],
+) -> Result<(T, bool), SchemaError> {
+    debug_assert_eq!(migrations.len(), current as usize);
+    let mut document: Value = serde_json::from_str(contents)
+        .map_err(|e| SchemaError::Invalid(format!("Invalid JSON: {}", e)))?;
+    let found = match document.get("version") {
+        None => 0,
+        Some(version) => version
+            .as_u64()
+            .ok_or_else(|| SchemaError::Invalid(format!("Invalid schema version {}", version)))?,
+    };
+    if found > current as u64 {
+        return Err(SchemaError::TooNew {
+            found,
+            supported: current,
+        });
+    }
+    for version in found..current as u64 {
+        document = migrations[version as usize](document).map_err(|e| {
+            SchemaError::Invalid(format!("Migration from version {} failed: {}", version, e))
+        })?;
+        if let Some(object) = document.as_object_mut() {
+            object.insert("version".to_string(), Value::from(version + 1));
+        }
+    }
+    let value =
+        serde_json::from_value(document).map_err(|e| SchemaError::Invalid(e.to_string()))?;
+    Ok((value, found < current as u64))
+}
+
+/// Migration for files written before they carried a `version` field.
+pub fn stamp_version(document: Value) -> Result<Value, String> {
+    if document.is_object() {
+        Ok(document)
+    } else {
+        Err("Expected a JSON object".to_string())
+    }
+}
+
+/// An empty directory for one test, removed and recreated on every run.
+#[cfg(test)]
+pub fn test_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("the-gatherer-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
@@ -0,0 +1,453 @@
+use crate::vector2::Vector2;
+use crate::world::{WorldEvent, WorldSnapshot};
+use serde::{Deserialize, Serialize};
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+use std::fmt;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+/// Bumped whenever the frame layout changes, so clients can refuse frames
+/// they cannot apply.
+pub const SYNC_PROTOCOL_VERSION: u32 = 1;
+
+/// Frames kept while waiting for an ack. A client this far behind is treated
+/// as desynced and gets full frames until it acks again.
+const MAX_UNACKED_FRAMES: usize = 64;
+
+/// An entity's serialized fields by name.
+pub type Fields = Map<String, Value>;
+
+/// The synced part of a world snapshot, each entity reduced to its fields.
+#[derive(Clone, Default)]
+pub struct SyncState {
+    base: Vector2,
+    resources: BTreeMap<String, Fields>,
+    players: BTreeMap<String, Fields>,
+}
+
+/// `null` marks a removed field in patches, so unset fields are left out
+/// rather than stored as `null`.
+fn fields_of(entity: &impl Serialize) -> Fields {
+    match serde_json::to_value(entity) {
+        Ok(Value::Object(fields)) => fields
+            .into_iter()
+            .filter(|(_, value)| !value.is_null())
+            .collect(),
+        _ => Fields::new(),
+    }
+}
+
+impl SyncState {
+    pub fn players(&self) -> &BTreeMap<String, Fields> {
+        &self.players
+    }
+}
+
+impl From<&WorldSnapshot> for SyncState {
+    fn from(snapshot: &WorldSnapshot) -> Self {
+        Self {
+            base: snapshot.base,
+            resources: snapshot
+                .resources
+                .iter()
+                .map(|node| (node.id.clone(), fields_of(node)))
+                .collect(),
+            players: snapshot
+                .players
+                .iter()
+                .map(|player| (player.id.clone(), fields_of(player)))
+                .collect(),
+        }
+    }
+}
+
+/// Fields of one entity. Complete for entities new to the client, otherwise
+/// only the ones that changed, with `null` for fields that were dropped.
+#[derive(Clone, Serialize, Deserialize)]
+pub struct EntityPatch {
+    pub id: String,
+    pub fields: Fields,
+}
+
+/// One message of the sync protocol. A frame with a `baseline` is applied on
+/// top of the state the client had at that sequence; a frame without one
+/// replaces everything the client knows.
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct SyncFrame {
+    pub version: u32,
+    pub sequence: u64,
+    pub baseline: Option<u64>,
+    pub tick: u64,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub base: Option<Vector2>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub resources: Vec<EntityPatch>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub players: Vec<EntityPatch>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_resources: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub removed_players: Vec<String>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    pub events: Vec<WorldEvent>,
+}
+
+impl SyncFrame {
+    /// Full frames are never empty, they always replace the client's state.
+    fn is_empty(&self) -> bool {
+        self.baseline.is_some()
+            && self.base.is_none()
+            && self.resources.is_empty()
+            && self.players.is_empty()
+            && self.removed_resources.is_empty()
+            && self.removed_players.is_empty()
+            && self.events.is_empty()
+    }
+}
+
+/// Patches turning `old` into `new`, and the ids only `old` has.
+fn diff_entities(
+    old: Option<&BTreeMap<String, Fields>>,
+    new: &BTreeMap<String, Fields>,
+) -> (Vec<EntityPatch>, Vec<String>) {
+    let mut patches = Vec::new();
+    for (id, fields) in new {
+        let changed = match old.and_then(|old| old.get(id)) {
+            None => fields.clone(),
+            Some(previous) => {
+                let mut changed: Fields = fields
+                    .iter()
+                    .filter(|(name, value)| previous.get(*name) != Some(*value))
+                    .map(|(name, value)| (name.clone(), value.clone()))
+                    .collect();
+                for name in previous.keys() {
+                    if !fields.contains_key(name) {
+                        changed.insert(name.clone(), Value::Null);
+                    }
+                }
+                changed
+            }
+        };
+        if !changed.is_empty() {
+            patches.push(EntityPatch {
+                id: id.clone(),
+                fields: changed,
+            });
+        }
+    }
+    let removed = old
+        .map(|old| {
+            old.keys()
+                .filter(|id| !new.contains_key(*id))
+                .cloned()
+                .collect()
+        })
+        .unwrap_or_default();
+    (patches, removed)
+}
+
+/// Server side of one client's sync stream. Every frame is a delta against
+/// the newest state the client acknowledged, so lost or late acks only make
+/// frames bigger, never wrong.
+#[derive(Default)]
+pub struct SyncEncoder {
+    sequence: u64,
+    acked: Option<u64>,
+    /// States sent since the last ack, by sequence, the acked one included.
+    sent: BTreeMap<u64, SyncState>,
+}
+
+impl SyncEncoder {
+    /// The frame bringing the client to `snapshot`, or `None` when the client
+    /// already has it and no event concerns it. Only events about entities
+    /// the client has or is about to get are kept.
+    pub fn encode(
+        &mut self,
+        tick: u64,
+        snapshot: &WorldSnapshot,
+        events: &[WorldEvent],
+    ) -> Option<SyncFrame> {
+        if self.sent.len() >= MAX_UNACKED_FRAMES {
+            self.reset();
+        }
+        let state = SyncState::from(snapshot);
+        let baseline = self
+            .acked
+            .and_then(|sequence| Some((sequence, self.sent.get(&sequence)?)));
+        let (resources, removed_resources) =
+            diff_entities(baseline.map(|(_, old)| &old.resources), &state.resources);
+        let (players, removed_players) =
+            diff_entities(baseline.map(|(_, old)| &old.players), &state.players);
+        let known = |id: &str| {
+            state.resources.contains_key(id)
+                || state.players.contains_key(id)
+                || baseline.is_some_and(|(_, old)| {
+                    old.resources.contains_key(id) || old.players.contains_key(id)
+                })
+        };
+        let frame = SyncFrame {
+            version: SYNC_PROTOCOL_VERSION,
+            sequence: self.sequence + 1,
+            baseline: baseline.map(|(sequence, _)| sequence),
+            tick,
+            base: match baseline {
+                Some((_, old)) if old.base == state.base => None,
+                _ => Some(state.base),
+            },
+            resources,
+            players,
+            removed_resources,
+            removed_players,
+            events: events
+                .iter()
+                .filter(|event| {
+                    event.resource_id().is_some_and(known) || event.player_id().is_some_and(known)
+                })
+                .cloned()
+                .collect(),
+        };
+        if frame.is_empty() {
+            return None;
+        }
+        self.sequence = frame.sequence;
+        self.sent.insert(frame.sequence, state);
+        Some(frame)
+    }
+
+    /// `encode` for a reliable, ordered channel such as Tauri events. Every
+    /// frame arrives, so the next one builds on it without waiting for an ack.
+    #[cfg(any(feature = "gui", test))]
+    pub fn encode_reliable(
+        &mut self,
+        tick: u64,
+        snapshot: &WorldSnapshot,
+        events: &[WorldEvent],
+    ) -> Option<SyncFrame> {
+        let frame = self.encode(tick, snapshot, events)?;
+        self.ack(frame.sequence);
+        Some(frame)
+    }
+
+    /// A frame replacing everything the client has, for joins and desyncs.
+    pub fn full(&mut self, tick: u64, snapshot: &WorldSnapshot) -> SyncFrame {
+        self.reset();
+        self.encode(tick, snapshot, &[])
+            .expect("full frames are never empty")
+    }
+
+    /// Records that the client applied frame `sequence`. Acks for frames
+    /// that were never sent or are older than the last ack are ignored.
+    pub fn ack(&mut self, sequence: u64) {
+        if !self.sent.contains_key(&sequence) || self.acked.is_some_and(|acked| acked >= sequence) {
+            return;
+        }
+        self.acked = Some(sequence);
+        self.sent = self.sent.split_off(&sequence);
+    }
+
+    /// Forgets the client's state so the next frame is a full one.
+    pub fn reset(&mut self) {
+        self.acked = None;
+        self.sent.clear();
+    }
+}
+
+#[derive(Debug)]
+pub enum SyncError {
+    UnsupportedVersion(u32),
+    /// The frame builds on a state this client does not have.
+    UnknownBaseline(u64),
+}
+
+impl fmt::Display for SyncError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            SyncError::UnsupportedVersion(version) => write!(
+                f,
+                "Sync protocol version {} is not supported, expected {}",
+                version, SYNC_PROTOCOL_VERSION
+            ),
+            SyncError::UnknownBaseline(sequence) => {
+                write!(f, "Frame builds on unknown state {}", sequence)
+            }
+        }
+    }
+}
+
+impl std::error::Error for SyncError {}
+
+fn patch_entities(
+    entities: &mut BTreeMap<String, Fields>,
+    patches: &[EntityPatch],
+    removed: &[String],
+) {
+    for id in removed {
+        entities.remove(id);
+    }
+    for patch in patches {
+        let fields = entities.entry(patch.id.clone()).or_default();
+        for (name, value) in &patch.fields {
+            if value.is_null() {
+                fields.remove(name);
+            } else {
+                fields.insert(name.clone(), value.clone());
+            }
+        }
+    }
+}
+
+/// Client side of a sync stream, rebuilding the states the encoder sent.
+#[derive(Default)]
+pub struct SyncDecoder {
+    /// Decoded states by sequence, from the newest baseline on.
+    states: BTreeMap<u64, SyncState>,
+}
+
+impl SyncDecoder {
+    /// Applies `frame` on top of its baseline and returns the new state. After
+    /// an error the client should ask for a full frame.
+    pub fn apply(&mut self, frame: &SyncFrame) -> Result<&SyncState, SyncError> {
+        if frame.version != SYNC_PROTOCOL_VERSION {
+            return Err(SyncError::UnsupportedVersion(frame.version));
+        }
+        let mut state = match frame.baseline {
+            Some(baseline) => self
+                .states
+                .get(&baseline)
+                .cloned()
+                .ok_or(SyncError::UnknownBaseline(baseline))?,
+            None => SyncState::default(),
+        };
+        if let Some(base) = frame.base {
+            state.base = base;
+        }
+        patch_entities(
+            &mut state.resources,
+            &frame.resources,
+            &frame.removed_resources,
+        );
+        patch_entities(&mut state.players, &frame.players, &frame.removed_players);
+
+        // Baselines only move forward, so older states are never needed again.
+        match frame.baseline {
+            Some(baseline) => self.states = self.states.split_off(&baseline),
+            None => self.states.clear(),
+        }
+        self.states.insert(frame.sequence, state);
+        Ok(&self.states[&frame.sequence])
+    }
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn ack_sync(encoder: State<'_, Mutex<SyncEncoder>>, sequence: u64) {
+    encoder.lock().unwrap().ack(sequence);
+}
+
+/// Asks for a full frame on the next tick after the frontend lost track.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn request_resync(encoder: State<'_, Mutex<SyncEncoder>>) {
+    encoder.lock().unwrap().reset();
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::map::MapData;
+    use crate::world::World;
+
+    fn world() -> World {
+        let mut world = World::new();
+        world.load_map(&MapData {
+            locations: Vec::new(),
+            base: None,
+            obstacles: Vec::new(),
+        });
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        world
+    }
+
+    fn position(state: &SyncState, player_id: &str) -> Value {
+        state.players()[player_id]["position"].clone()
+    }
+
+    #[test]
+    fn reliable_frames_build_on_the_previous_one() {
+        let mut world = world();
+        let mut encoder = SyncEncoder::default();
+        let mut decoder = SyncDecoder::default();
+
+        let first = encoder.encode_reliable(1, &world.snapshot(), &[]).unwrap();
+        assert_eq!(first.baseline, None);
+        decoder.apply(&first).unwrap();
+        assert!(encoder.encode_reliable(2, &world.snapshot(), &[]).is_none());
+
+        world
+            .set_move_target("a", Vector2::new(900.0, 500.0))
+            .unwrap();
+        world.step(3, 0.1);
+        let moved = encoder.encode_reliable(3, &world.snapshot(), &[]).unwrap();
+        assert_eq!(moved.baseline, Some(first.sequence));
+        assert!(moved.base.is_none());
+        assert_eq!(moved.players.len(), 1);
+        assert!(!moved.players[0].fields.contains_key("displayName"));
+
+        let state = decoder.apply(&moved).unwrap();
+        let expected = SyncState::from(&world.snapshot());
+        assert_eq!(state.players(), expected.players());
+    }
+
+    #[test]
+    fn unacked_frames_build_on_the_last_ack() {
+        let mut world = world();
+        let mut encoder = SyncEncoder::default();
+        let first = encoder.encode(1, &world.snapshot(), &[]).unwrap();
+        encoder.ack(first.sequence);
+
+        world
+            .set_move_target("a", Vector2::new(900.0, 500.0))
+            .unwrap();
+        world.step(2, 0.1);
+        let second = encoder.encode(2, &world.snapshot(), &[]).unwrap();
+        world.step(3, 0.1);
+        let third = encoder.encode(3, &world.snapshot(), &[]).unwrap();
+        assert_eq!(second.baseline, Some(first.sequence));
+        assert_eq!(third.baseline, Some(first.sequence));
+
+        // A client that lost `second` still applies `third`.
+        let mut decoder = SyncDecoder::default();
+        decoder.apply(&first).unwrap();
+        let state = decoder.apply(&third).unwrap();
+        assert_eq!(
+            position(state, "a"),
+            position(&SyncState::from(&world.snapshot()), "a")
+        );
+    }
+
+    #[test]
+    fn frames_on_unknown_states_are_rejected() {
+        let mut world = world();
+        let mut encoder = SyncEncoder::default();
+        let first = encoder.encode_reliable(1, &world.snapshot(), &[]).unwrap();
+        world
+            .set_move_target("a", Vector2::new(900.0, 500.0))
+            .unwrap();
+        world.step(2, 0.1);
+        let second = encoder.encode_reliable(2, &world.snapshot(), &[]).unwrap();
+
+        let mut decoder = SyncDecoder::default();
+        assert!(matches!(
+            decoder.apply(&second),
+            Err(SyncError::UnknownBaseline(sequence)) if sequence == first.sequence
+        ));
+        encoder.reset();
+        let full = encoder.encode_reliable(3, &world.snapshot(), &[]).unwrap();
+        assert_eq!(full.baseline, None);
+        assert!(decoder.apply(&full).is_ok());
+    }
+}
@@ -0,0 +1,76 @@
+#[cfg(feature = "gui")]
+use crate::sync::{SyncEncoder, SyncFrame};
+#[cfg(feature = "gui")]
+use crate::world::World;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::{AppHandle, Emitter, Manager};
+use tokio::time::{interval, Duration, Instant, MissedTickBehavior};
+
+#[cfg(feature = "gui")]
+pub const WORLD_SYNC_EVENT: &str = "world-sync";
+
+/// Simulation steps per second.
+pub const TICK_RATE: u32 = 20;
+
+/// Upper bound on catch-up steps after the process was stalled, so a long
+/// suspend does not freeze the loop while it replays every missed tick.
+const MAX_STEPS_PER_TICK: u32 = 10;
+
+/// Fixed-timestep loop advancing the world independently of the webview's
+/// frame rate. Sync frames are emitted to the `main` window, each a delta
+/// against the one before.
+#[cfg(feature = "gui")]
+pub async fn run(app: AppHandle) {
+    run_loop(|tick, delta_time| {
+        let world = app.state::<Mutex<World>>();
+        let mut world = world.lock().unwrap();
+        let delta = world.step(tick, delta_time);
+        let frame = app
+            .state::<Mutex<SyncEncoder>>()
+            .lock()
+            .unwrap()
+            .encode_reliable(tick, &world.snapshot(), &delta.events);
+        if let Some(frame) = frame {
+            emit_frame(&app, frame);
+        }
+    })
+    .await;
+}
+
+#[cfg(feature = "gui")]
+fn emit_frame(app: &AppHandle, frame: SyncFrame) {
+    if let Err(e) = app.emit_to("main", WORLD_SYNC_EVENT, frame) {
+        println!("Failed to emit sync frame: {}", e);
+    }
+}
+
+/// Calls `on_step` with the tick number and a fixed delta time, `TICK_RATE`
+/// times per second of real time.
+pub async fn run_loop(mut on_step: impl FnMut(u64, f64)) {
+    let step = Duration::from_secs(1) / TICK_RATE;
+    let mut ticker = interval(step);
+    ticker.set_missed_tick_behavior(MissedTickBehavior::Skip);
+
+    let mut tick: u64 = 0;
+    let mut accumulator = Duration::ZERO;
+    let mut last = Instant::now();
+    loop {
+        ticker.tick().await;
+        let now = Instant::now();
+        accumulator += now - last;
+        last = now;
+
+        let mut steps = 0;
+        while accumulator >= step && steps < MAX_STEPS_PER_TICK {
+            accumulator -= step;
+            steps += 1;
+            tick += 1;
+            on_step(tick, step.as_secs_f64());
+        }
+        if steps == MAX_STEPS_PER_TICK {
+            accumulator = Duration::ZERO;
+        }
+    }
+}
@@ -0,0 +1,123 @@
+use serde::{Deserialize, Serialize};
+use std::sync::OnceLock;
+
+/// Tier table bundled with the frontend. A tier's position in the list is
+/// the rarity value used by maps, inventories and recipes.
+pub const DEFAULT_TIERS: &str = include_str!("../../src/data/tiers.json");
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct TierDefinition {
+    pub id: u8,
+    /// Translation key of the tier's display name.
+    pub name_key: String,
+    pub shard_value: u32,
+    /// Seconds of gathering needed to harvest a node.
+    pub gather_time: f64,
+    /// Seconds until a harvested node is available again.
+    pub refill_time: f64,
+    pub color: String,
+    /// Most nodes a map may hold, `None` for no limit.
+    pub cap: Option<u32>,
+    /// Most resources a player may carry. `None` leaves the tier bounded
+    /// only by `MAX_TOTAL_SHARDS`.
+    pub carry_cap: Option<u32>,
+    /// Nodes the generator places by default.
+    pub spawn_count: u32,
+    pub texture: String,
+}
+
+#[derive(Deserialize)]
+struct TierFile {
+    tiers: Vec<TierDefinition>,
+}
+
+static TIERS: OnceLock<Vec<TierDefinition>> = OnceLock::new();
+
+pub fn parse_tiers(json: &str) -> Result<Vec<TierDefinition>, String> {
+    let file: TierFile =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse tiers: {}", e))?;
+    if file.tiers.is_empty() {
+        return Err("Tier table is empty".to_string());
+    }
+    if file.tiers.len() > u8::MAX as usize {
+        return Err(format!("Expected at most {} tiers", u8::MAX));
+    }
+    for (index, tier) in file.tiers.iter().enumerate() {
+        if tier.id as usize != index {
+            return Err(format!("Tier {} is listed at position {}", tier.id, index));
+        }
+        if tier.shard_value == 0 {
+            return Err(format!("Tier {} has no shard value", tier.id));
+        }
+        if tier.gather_time <= 0.0 || tier.refill_time < 0.0 {
+            return Err(format!(
+                "Tier {} has an invalid gather or refill time",
+                tier.id
+            ));
+        }
+        if tier.texture.is_empty() {
+            return Err(format!("Tier {} has no texture", tier.id));
+        }
+    }
+    Ok(file.tiers)
+}
+
+/// Parses the bundled table once, so a broken file fails startup with a
+/// readable error instead of a panic in the first lookup.
+pub fn load() -> Result<&'static [TierDefinition], String> {
+    if let Some(tiers) = TIERS.get() {
+        return Ok(tiers);
+    }
+    let tiers = parse_tiers(DEFAULT_TIERS)?;
+    Ok(TIERS.get_or_init(|| tiers))
+}
+
+pub fn tiers() -> &'static [TierDefinition] {
+    load().expect("bundled tier table is invalid")
+}
+
+pub fn tier(rarity: u8) -> Option<&'static TierDefinition> {
+    tiers().get(rarity as usize)
+}
+
+pub fn tier_count() -> u8 {
+    tiers().len() as u8
+}
+
+/// Shard value of one resource of `rarity`, unknown rarities count as the
+/// lowest tier.
+pub fn shard_value(rarity: u8) -> u32 {
+    tier(rarity).unwrap_or(&tiers()[0]).shard_value
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn get_tier_definitions() -> Result<Vec<TierDefinition>, String> {
+    load().map(|tiers| tiers.to_vec())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn bundled_table_has_every_frontend_tier() {
+        let tiers = parse_tiers(DEFAULT_TIERS).unwrap();
+        // `ResourceRarity` in the frontend runs from COMMON to MYTHICAL.
+        assert_eq!(tiers.len(), 6);
+        let mythical = &tiers[5];
+        assert_eq!(mythical.name_key, "game.resources.mythical");
+        assert_eq!(mythical.shard_value, 1000);
+        assert_eq!(mythical.color, "#D35400");
+        assert_eq!(mythical.cap, Some(1));
+        assert_eq!(mythical.carry_cap, Some(1));
+    }
+
+    #[test]
+    fn tiers_must_be_listed_in_order() {
+        let json = r##"{"tiers": [{"id": 1, "nameKey": "a", "shardValue": 1, "gatherTime": 1,
+            "refillTime": 1, "color": "#fff", "cap": null, "carryCap": null, "spawnCount": 1, "texture": "t.png"}]}"##;
+        assert!(parse_tiers(json).is_err());
+    }
+}
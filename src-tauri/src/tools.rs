@@ -0,0 +1,130 @@
+use crate::constants::RESOURCE_GATHER_DISTANCE;
+#[cfg(feature = "gui")]
+use crate::world::{World, WorldError};
+use serde::{Deserialize, Serialize};
+use std::collections::{BTreeMap, HashSet};
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+/// Tool definitions bundled with the frontend. Tools are obtained as crafted
+/// items with the same id.
+pub const DEFAULT_TOOLS: &str = include_str!("../../src/data/tools.json");
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ToolDefinition {
+    pub id: String,
+    pub name: String,
+    /// Multiplier on gather progress, 1.25 gathers 25% faster.
+    #[serde(default = "default_gather_speed")]
+    pub gather_speed: f64,
+    /// Added to `RESOURCE_GATHER_DISTANCE`.
+    #[serde(default)]
+    pub range_bonus: f64,
+    /// Chance from 0 to 1 that a gather yields one extra resource.
+    #[serde(default)]
+    pub extra_yield_chance: f64,
+}
+
+fn default_gather_speed() -> f64 {
+    1.0
+}
+
+#[derive(Deserialize)]
+struct ToolFile {
+    tools: Vec<ToolDefinition>,
+}
+
+/// Combined effect of every tool a player owns. Owning several copies of a
+/// tool counts once.
+#[derive(Clone, Copy, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatherStats {
+    pub gather_speed: f64,
+    pub gather_distance: f64,
+    pub extra_yield_chance: f64,
+}
+
+impl Default for GatherStats {
+    fn default() -> Self {
+        Self {
+            gather_speed: 1.0,
+            gather_distance: RESOURCE_GATHER_DISTANCE,
+            extra_yield_chance: 0.0,
+        }
+    }
+}
+
+impl GatherStats {
+    pub fn with(self, tool: &ToolDefinition) -> Self {
+        Self {
+            gather_speed: self.gather_speed * tool.gather_speed,
+            gather_distance: self.gather_distance + tool.range_bonus,
+            // Independent rolls, so the chance that none of them hits
+            // shrinks with every tool.
+            extra_yield_chance: 1.0
+                - (1.0 - self.extra_yield_chance) * (1.0 - tool.extra_yield_chance),
+        }
+    }
+}
+
+/// Tool definitions keyed by id.
+pub type ToolTable = BTreeMap<String, ToolDefinition>;
+
+pub fn parse_tools(json: &str) -> Result<ToolTable, String> {
+    let file: ToolFile =
+        serde_json::from_str(json).map_err(|e| format!("Failed to parse tools: {}", e))?;
+    let mut ids = HashSet::new();
+    for tool in &file.tools {
+        if !ids.insert(tool.id.as_str()) {
+            return Err(format!("Tool '{}' is defined twice", tool.id));
+        }
+        if tool.gather_speed <= 0.0 {
+            return Err(format!(
+                "Tool '{}' must have a positive gather speed",
+                tool.id
+            ));
+        }
+        if tool.range_bonus < 0.0 {
+            return Err(format!("Tool '{}' has a negative range bonus", tool.id));
+        }
+        if !(0.0..=1.0).contains(&tool.extra_yield_chance) {
+            return Err(format!(
+                "Tool '{}' has an extra yield chance outside 0 to 1",
+                tool.id
+            ));
+        }
+    }
+    Ok(file
+        .tools
+        .into_iter()
+        .map(|tool| (tool.id.clone(), tool))
+        .collect())
+}
+
+/// Stats for a player owning `items`.
+pub fn gather_stats(tools: &ToolTable, items: &BTreeMap<String, u32>) -> GatherStats {
+    items
+        .iter()
+        .filter(|(_, count)| **count > 0)
+        .filter_map(|(item, _)| tools.get(item))
+        .fold(GatherStats::default(), GatherStats::with)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn list_tools(world: State<'_, Mutex<World>>) -> Vec<ToolDefinition> {
+    world.lock().unwrap().tools().values().cloned().collect()
+}
+
+/// The player's gathering stats with all owned tools applied.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn get_gather_stats(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+) -> Result<GatherStats, WorldError> {
+    world.lock().unwrap().gather_stats(&player_id)
+}
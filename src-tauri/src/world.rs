@@ -0,0 +1,1604 @@
+use crate::constants::{
+    BORDER_WIDTH, MAP_HEIGHT, MAP_WIDTH, PLAYER_SPEED, RESOURCE_GATHER_DISTANCE,
+};
+use crate::crafting::{self, Recipe};
+use crate::generator::SeededRng;
+use crate::inventory::{self, Capacity, Inventory};
+use crate::map::{self, MapData};
+use crate::movement::Motion;
+use crate::pathfinding::WalkGrid;
+use crate::spatial::SpatialGrid;
+use crate::tiers;
+use crate::tools::{self, GatherStats, ToolTable};
+use crate::vector2::Vector2;
+use serde::ser::SerializeStruct;
+use serde::{Deserialize, Serialize, Serializer};
+use std::collections::{BTreeMap, BTreeSet};
+use std::fmt;
+#[cfg(feature = "gui")]
+use std::sync::Mutex;
+#[cfg(feature = "gui")]
+use tauri::State;
+
+#[derive(Clone, Serialize)]
+#[serde(
+    tag = "state",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum NodeState {
+    Available,
+    Gathering {
+        player_id: String,
+        player_name: String,
+        progress: f64,
+    },
+    Refilling {
+        progress: f64,
+    },
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct ResourceNode {
+    pub id: String,
+    pub position: Vector2,
+    pub rarity: u8,
+    pub shard_value: u32,
+    /// Seconds of uninterrupted gathering needed to harvest the node.
+    pub gather_time: f64,
+    /// Seconds until a harvested node becomes available again.
+    pub refill_time: f64,
+    #[serde(flatten)]
+    pub state: NodeState,
+}
+
+impl ResourceNode {
+    pub fn new(x: f64, y: f64, rarity: u8) -> Self {
+        // Maps are validated against the tier table, so the fallback only
+        // covers rarities from maps built in code.
+        let tier = tiers::tier(rarity).unwrap_or(&tiers::tiers()[0]);
+        Self {
+            id: format!("resource_{}_{}_{}", x, y, rarity),
+            position: Vector2::new(x, y),
+            rarity,
+            shard_value: tier.shard_value,
+            gather_time: tier.gather_time,
+            refill_time: tier.refill_time,
+            state: NodeState::Available,
+        }
+    }
+
+    pub fn is_available(&self) -> bool {
+        matches!(self.state, NodeState::Available)
+    }
+}
+
+#[derive(Clone, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct PlayerState {
+    pub id: String,
+    pub display_name: String,
+    pub position: Vector2,
+    /// Point the player is walking towards, if any.
+    pub target: Option<Vector2>,
+    /// Remaining waypoints to `target`, ending with it.
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub path: Vec<Vector2>,
+    /// Resource currently being gathered, if any.
+    pub gathering: Option<String>,
+    /// Carried resource counts keyed by rarity.
+    pub inventory: Inventory,
+    /// Lifetime gather counts keyed by rarity.
+    pub gathered: BTreeMap<u8, u32>,
+    /// Resources stored at the base, which do not count against capacity.
+    pub bank: Inventory,
+    /// Crafted item counts keyed by item id.
+    pub items: BTreeMap<String, u32>,
+    /// Sequence number of the last movement input applied, so a predicting
+    /// client knows which of its inputs are reflected in this state.
+    pub last_input: u64,
+    /// World time of the player's last command, used to expire leases held
+    /// by clients that went away without leaving.
+    #[serde(skip)]
+    pub last_seen: f64,
+    /// World time the position was last set from outside the simulation,
+    /// used to bound how far the next reported position may be.
+    #[serde(skip)]
+    pub position_time: f64,
+    /// Distance reported positions may still cover as of `position_time`.
+    /// Refills at `PLAYER_SPEED` up to `MAX_POSITION_BUDGET` and is spent by
+    /// every accepted report.
+    #[serde(skip)]
+    pub position_budget: f64,
+}
+
+impl PlayerState {
+    pub fn motion(&self) -> Motion {
+        Motion {
+            position: self.position,
+            target: self.target,
+            path: self.path.clone(),
+        }
+    }
+
+    pub fn set_motion(&mut self, motion: Motion) {
+        self.position = motion.position;
+        self.target = motion.target;
+        self.path = motion.path;
+    }
+}
+
+/// The part of a player that outlives a session and goes into saves.
+#[derive(Clone, Default, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Progress {
+    pub inventory: Inventory,
+    pub gathered: BTreeMap<u8, u32>,
+    pub bank: Inventory,
+    pub items: BTreeMap<String, u32>,
+}
+
+/// Exclusive right to gather a node, held until the gather completes, the
+/// holder walks out of range, leaves, or stops sending commands.
+#[derive(Clone, Debug, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct GatherLease {
+    pub resource_id: String,
+    pub holder_id: String,
+    pub holder_name: String,
+}
+
+#[derive(Clone, Serialize, Deserialize)]
+#[serde(
+    tag = "type",
+    rename_all = "camelCase",
+    rename_all_fields = "camelCase"
+)]
+pub enum WorldEvent {
+    Gathered {
+        player_id: String,
+        resource_id: String,
+        rarity: u8,
+        /// More than one when a tool granted extra yield.
+        amount: u32,
+    },
+    GatherCancelled {
+        player_id: String,
+        resource_id: String,
+    },
+    Refilled {
+        resource_id: String,
+    },
+    Arrived {
+        player_id: String,
+    },
+    LeaseExpired {
+        player_id: String,
+        resource_id: String,
+    },
+}
+
+impl WorldEvent {
+    pub fn player_id(&self) -> Option<&str> {
+        match self {
+            WorldEvent::Gathered { player_id, .. }
+            | WorldEvent::GatherCancelled { player_id, .. }
+            | WorldEvent::Arrived { player_id }
+            | WorldEvent::LeaseExpired { player_id, .. } => Some(player_id),
+            WorldEvent::Refilled { .. } => None,
+        }
+    }
+
+    pub fn resource_id(&self) -> Option<&str> {
+        match self {
+            WorldEvent::Gathered { resource_id, .. }
+            | WorldEvent::GatherCancelled { resource_id, .. }
+            | WorldEvent::Refilled { resource_id }
+            | WorldEvent::LeaseExpired { resource_id, .. } => Some(resource_id),
+            WorldEvent::Arrived { .. } => None,
+        }
+    }
+}
+
+#[derive(Debug)]
+pub enum WorldError {
+    UnknownPlayer(String),
+    UnknownResource(String),
+    OutOfRange {
+        resource_id: String,
+        distance: f64,
+        reach: f64,
+    },
+    NotAvailable(String),
+    AlreadyClaimed(GatherLease),
+    InventoryFull {
+        rarity: u8,
+    },
+    TooFarFromBase {
+        distance: f64,
+    },
+    NotEnough {
+        rarity: u8,
+        requested: u32,
+        available: u32,
+    },
+    UnknownRecipe(String),
+    ImpossibleMove {
+        distance: f64,
+        allowed: f64,
+    },
+    NoPath {
+        x: f64,
+        y: f64,
+    },
+}
+
+impl WorldError {
+    pub fn code(&self) -> &'static str {
+        match self {
+            WorldError::UnknownPlayer(_) => "unknown_player",
+            WorldError::UnknownResource(_) => "unknown_resource",
+            WorldError::OutOfRange { .. } => "out_of_range",
+            WorldError::NotAvailable(_) => "not_available",
+            WorldError::AlreadyClaimed(_) => "already_claimed",
+            WorldError::InventoryFull { .. } => "inventory_full",
+            WorldError::TooFarFromBase { .. } => "too_far_from_base",
+            WorldError::NotEnough { .. } => "not_enough",
+            WorldError::UnknownRecipe(_) => "unknown_recipe",
+            WorldError::ImpossibleMove { .. } => "impossible_move",
+            WorldError::NoPath { .. } => "no_path",
+        }
+    }
+}
+
+impl fmt::Display for WorldError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            WorldError::UnknownPlayer(id) => write!(f, "Unknown player '{}'", id),
+            WorldError::UnknownResource(id) => write!(f, "Unknown resource '{}'", id),
+            WorldError::OutOfRange {
+                resource_id,
+                distance,
+                reach,
+            } => write!(
+                f,
+                "Resource '{}' is {:.0} away, gather distance is {:.0}",
+                resource_id, distance, reach
+            ),
+            WorldError::NotAvailable(id) => write!(f, "Resource '{}' is not available", id),
+            WorldError::AlreadyClaimed(lease) => write!(
+                f,
+                "Resource '{}' is already claimed by {}",
+                lease.resource_id, lease.holder_name
+            ),
+            WorldError::InventoryFull { rarity } => {
+                write!(f, "No room to carry another tier {} resource", rarity + 1)
+            }
+            WorldError::TooFarFromBase { distance } => write!(
+                f,
+                "Base is {:.0} away, bank distance is {:.0}",
+                distance, BANK_DISTANCE
+            ),
+            WorldError::NotEnough {
+                rarity,
+                requested,
+                available,
+            } => write!(
+                f,
+                "Requested {} tier {} resources but only {} are there",
+                requested,
+                rarity + 1,
+                available
+            ),
+            WorldError::UnknownRecipe(id) => write!(f, "Unknown recipe '{}'", id),
+            WorldError::ImpossibleMove { distance, allowed } => write!(
+                f,
+                "Moved {:.0} but at most {:.0} was possible",
+                distance, allowed
+            ),
+            WorldError::NoPath { x, y } => write!(f, "({:.0}, {:.0}) cannot be reached", x, y),
+        }
+    }
+}
+
+impl std::error::Error for WorldError {}
+
+// Commands hand errors to the frontend as `{ code, message, lease }`, where
+// `lease` names the current holder for `already_claimed`.
+impl Serialize for WorldError {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let lease = match self {
+            WorldError::AlreadyClaimed(lease) => Some(lease),
+            _ => None,
+        };
+        let mut state = serializer.serialize_struct("WorldError", 3)?;
+        state.serialize_field("code", self.code())?;
+        state.serialize_field("message", &self.to_string())?;
+        state.serialize_field("lease", &lease)?;
+        state.end()
+    }
+}
+
+/// Changes produced by one simulation step: the events plus the current
+/// state of every node and player they touched or that has a running timer.
+#[derive(Clone, Serialize)]
+pub struct WorldDelta {
+    pub tick: u64,
+    pub events: Vec<WorldEvent>,
+    pub resources: Vec<ResourceNode>,
+    pub players: Vec<PlayerState>,
+}
+
+impl WorldDelta {
+    pub fn is_empty(&self) -> bool {
+        self.events.is_empty() && self.resources.is_empty() && self.players.is_empty()
+    }
+}
+
+/// Result of a proximity query, nearest first for radius queries.
+#[derive(Clone, Serialize)]
+pub struct Nearby {
+    pub resources: Vec<ResourceNode>,
+    pub players: Vec<PlayerState>,
+}
+
+#[derive(Clone, Serialize)]
+pub struct WorldSnapshot {
+    pub base: Vector2,
+    pub resources: Vec<ResourceNode>,
+    pub players: Vec<PlayerState>,
+}
+
+/// Seconds without any command after which a player's gather lease lapses.
+/// Longer than the slowest gather so active players never hit it.
+pub const LEASE_TIMEOUT: f64 = 10.0;
+
+/// Slack on top of `PLAYER_SPEED` for reported positions, covering latency
+/// and frame timing differences between client and backend. Spent once and
+/// only built back up by walking slower than `PLAYER_SPEED`.
+const POSITION_TOLERANCE: f64 = 50.0;
+
+/// Longest gap allowed between reported positions of a walking player.
+/// Clients report at least this often; a report covering more time is
+/// rejected, so standing still cannot bank time for a later jump.
+const MAX_POSITION_INTERVAL: f64 = 0.25;
+
+/// Most distance one or several back-to-back reported positions may cover.
+const MAX_POSITION_BUDGET: f64 = PLAYER_SPEED * MAX_POSITION_INTERVAL + POSITION_TOLERANCE;
+
+/// Clamps `position` into the map minus `BORDER_WIDTH`, the area players can
+/// walk on.
+pub fn clamp_to_playable(position: Vector2) -> Vector2 {
+    Vector2::new(
+        position.x.clamp(BORDER_WIDTH, MAP_WIDTH - BORDER_WIDTH),
+        position.y.clamp(BORDER_WIDTH, MAP_HEIGHT - BORDER_WIDTH),
+    )
+}
+
+/// How close a player must stand to the base to use the bank, the same reach
+/// as gathering.
+pub const BANK_DISTANCE: f64 = RESOURCE_GATHER_DISTANCE;
+
+/// Authoritative game state: resource nodes, their gather/refill timers and
+/// player inventories.
+#[derive(Default)]
+pub struct World {
+    nodes: BTreeMap<String, ResourceNode>,
+    players: BTreeMap<String, PlayerState>,
+    /// Node and player positions by id, for proximity queries.
+    node_index: SpatialGrid<String>,
+    player_index: SpatialGrid<String>,
+    /// Position of the shard bank.
+    base: Vector2,
+    /// Walkable area of the current map.
+    grid: WalkGrid,
+    /// Tools players can own, see `tools::gather_stats`.
+    tools: ToolTable,
+    /// Rolls for extra yield.
+    rng: SeededRng,
+    /// Players whose position changed outside `update`, included in the
+    /// next delta so every client sees the authoritative position.
+    moved: BTreeSet<String>,
+    /// Seconds simulated so far.
+    time: f64,
+    /// Number of the last step.
+    tick: u64,
+}
+
+impl World {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn set_tools(&mut self, tools: ToolTable) {
+        self.tools = tools;
+    }
+
+    pub fn tools(&self) -> &ToolTable {
+        &self.tools
+    }
+
+    pub fn gather_stats(&self, player_id: &str) -> Result<GatherStats, WorldError> {
+        let player = self
+            .players
+            .get(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        Ok(tools::gather_stats(&self.tools, &player.items))
+    }
+
+    pub fn load_map(&mut self, map: &MapData) {
+        self.nodes = map
+            .locations
+            .iter()
+            .map(|location| ResourceNode::new(location.x, location.y, location.rarity))
+            .map(|node| (node.id.clone(), node))
+            .collect();
+        self.node_index.clear();
+        for node in self.nodes.values() {
+            self.node_index.insert(node.id.clone(), node.position);
+        }
+        self.base = map.base.unwrap_or_else(map::spawn_point);
+        self.grid = WalkGrid::from_map(map);
+        for player in self.players.values_mut() {
+            player.gathering = None;
+            // Paths were planned around the old map's obstacles.
+            player.target = None;
+            player.path.clear();
+        }
+    }
+
+    pub fn join(&mut self, player_id: &str, display_name: &str, position: Vector2) {
+        let position = clamp_to_playable(position);
+        let player = self
+            .players
+            .entry(player_id.to_string())
+            .or_insert_with(|| PlayerState {
+                id: player_id.to_string(),
+                display_name: display_name.to_string(),
+                position,
+                target: None,
+                path: Vec::new(),
+                gathering: None,
+                inventory: BTreeMap::new(),
+                gathered: BTreeMap::new(),
+                bank: BTreeMap::new(),
+                items: BTreeMap::new(),
+                last_input: 0,
+                last_seen: 0.0,
+                position_time: 0.0,
+                position_budget: 0.0,
+            });
+        player.display_name = display_name.to_string();
+        player.position = position;
+        player.last_seen = self.time;
+        player.position_time = self.time;
+        player.position_budget = POSITION_TOLERANCE;
+        self.player_index.insert(player_id.to_string(), position);
+        self.moved.insert(player_id.to_string());
+    }
+
+    /// Records activity from a player, keeping its gather lease alive.
+    pub fn touch(&mut self, player_id: &str) -> Result<(), WorldError> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        player.last_seen = self.time;
+        Ok(())
+    }
+
+    pub fn tick(&self) -> u64 {
+        self.tick
+    }
+
+    pub fn base(&self) -> Vector2 {
+        self.base
+    }
+
+    pub fn resources(&self) -> impl Iterator<Item = &ResourceNode> {
+        self.nodes.values()
+    }
+
+    pub fn players(&self) -> impl Iterator<Item = &PlayerState> {
+        self.players.values()
+    }
+
+    pub fn has_player(&self, player_id: &str) -> bool {
+        self.players.contains_key(player_id)
+    }
+
+    pub fn player(&self, player_id: &str) -> Option<&PlayerState> {
+        self.players.get(player_id)
+    }
+
+    pub fn progress(&self, player_id: &str) -> Option<Progress> {
+        let player = self.players.get(player_id)?;
+        Some(Progress {
+            inventory: player.inventory.clone(),
+            gathered: player.gathered.clone(),
+            bank: player.bank.clone(),
+            items: player.items.clone(),
+        })
+    }
+
+    /// Replaces a player's progress, e.g. from a save.
+    pub fn restore_progress(
+        &mut self,
+        player_id: &str,
+        progress: Progress,
+    ) -> Result<(), WorldError> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        player.inventory = progress.inventory;
+        player.gathered = progress.gathered;
+        player.bank = progress.bank;
+        player.items = progress.items;
+        Ok(())
+    }
+
+    pub fn leave(&mut self, player_id: &str) {
+        self.stop_gathering(player_id);
+        self.players.remove(player_id);
+        self.player_index.remove(&player_id.to_string());
+    }
+
+    /// Player standing within `BANK_DISTANCE` of the base.
+    fn player_at_base(&mut self, player_id: &str) -> Result<&mut PlayerState, WorldError> {
+        self.touch(player_id)?;
+        let base = self.base;
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        let distance = player.position.distance_to(base);
+        if distance > BANK_DISTANCE {
+            return Err(WorldError::TooFarFromBase { distance });
+        }
+        Ok(player)
+    }
+
+    /// Moves carried resources into the bank. Without `rarity` every tier is
+    /// deposited, without `count` all of the tier.
+    pub fn deposit(
+        &mut self,
+        player_id: &str,
+        rarity: Option<u8>,
+        count: Option<u32>,
+    ) -> Result<PlayerState, WorldError> {
+        let player = self.player_at_base(player_id)?;
+        let rarities: Vec<u8> = match rarity {
+            Some(rarity) => vec![rarity],
+            None => player.inventory.keys().copied().collect(),
+        };
+        for rarity in &rarities {
+            let available = player.inventory.get(rarity).copied().unwrap_or(0);
+            let requested = count.unwrap_or(available);
+            if requested > available {
+                return Err(WorldError::NotEnough {
+                    rarity: *rarity,
+                    requested,
+                    available,
+                });
+            }
+        }
+        for rarity in rarities {
+            let available = player.inventory.remove(&rarity).unwrap_or(0);
+            let moved = count.unwrap_or(available);
+            if available > moved {
+                player.inventory.insert(rarity, available - moved);
+            }
+            if moved > 0 {
+                let banked = player.bank.entry(rarity).or_insert(0);
+                *banked = banked.saturating_add(moved);
+            }
+        }
+        Ok(player.clone())
+    }
+
+    /// Takes resources out of the bank, as long as they fit the carry limits.
+    pub fn withdraw(
+        &mut self,
+        player_id: &str,
+        rarity: u8,
+        count: u32,
+    ) -> Result<PlayerState, WorldError> {
+        let player = self.player_at_base(player_id)?;
+        let available = player.bank.get(&rarity).copied().unwrap_or(0);
+        if count > available {
+            return Err(WorldError::NotEnough {
+                rarity,
+                requested: count,
+                available,
+            });
+        }
+        if count > inventory::room_for(&player.inventory, rarity) {
+            return Err(WorldError::InventoryFull { rarity });
+        }
+        if available == count {
+            player.bank.remove(&rarity);
+        } else {
+            player.bank.insert(rarity, available - count);
+        }
+        if count > 0 {
+            *player.inventory.entry(rarity).or_insert(0) += count;
+        }
+        Ok(player.clone())
+    }
+
+    pub fn bank_contents(&self, player_id: &str) -> Result<Inventory, WorldError> {
+        self.players
+            .get(player_id)
+            .map(|player| player.bank.clone())
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))
+    }
+
+    pub fn craft(&mut self, player_id: &str, recipe: &Recipe) -> Result<PlayerState, WorldError> {
+        self.touch(player_id)?;
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        crafting::apply(player, recipe)?;
+        Ok(player.clone())
+    }
+
+    pub fn capacity(&self, player_id: &str) -> Result<Capacity, WorldError> {
+        let player = self
+            .players
+            .get(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        Ok(inventory::capacity(&player.inventory))
+    }
+
+    /// Accepts a client-reported position if the player could have walked
+    /// there in a straight line since the last one. Clients walking on their
+    /// own report every `MAX_POSITION_INTERVAL`. A rejected jump leaves the
+    /// player where the backend has it and sends that position back out with
+    /// the next delta.
+    pub fn set_player_position(
+        &mut self,
+        player_id: &str,
+        position: Vector2,
+    ) -> Result<(), WorldError> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        player.last_seen = self.time;
+        self.moved.insert(player_id.to_string());
+
+        let position = clamp_to_playable(position);
+        let distance = player.position.distance_to(position);
+        let elapsed = self.time - player.position_time;
+        let allowed = (player.position_budget + PLAYER_SPEED * elapsed).min(MAX_POSITION_BUDGET);
+        if distance > allowed {
+            return Err(WorldError::ImpossibleMove { distance, allowed });
+        }
+        if !self.grid.is_walkable(position) || !self.grid.segment_clear(player.position, position) {
+            return Err(WorldError::NoPath {
+                x: position.x,
+                y: position.y,
+            });
+        }
+        player.position = position;
+        player.position_time = self.time;
+        player.position_budget = allowed - distance;
+        self.player_index.insert(player_id.to_string(), position);
+        Ok(())
+    }
+
+    pub fn find_path(&self, from: Vector2, to: Vector2) -> Result<Vec<Vector2>, WorldError> {
+        self.grid
+            .find_path(from, to)
+            .ok_or(WorldError::NoPath { x: to.x, y: to.y })
+    }
+
+    /// Starts walking towards `target`, clamped to the playable area, along
+    /// a path around obstacles. Like the client, moving cancels any gathering
+    /// in progress.
+    pub fn set_move_target(&mut self, player_id: &str, target: Vector2) -> Result<(), WorldError> {
+        self.touch(player_id)?;
+        let mut motion = self
+            .players
+            .get(player_id)
+            .map(PlayerState::motion)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        motion.walk_to(&self.grid, target)?;
+        self.stop_gathering(player_id);
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.set_motion(motion);
+        }
+        Ok(())
+    }
+
+    /// Records that the player's movement input `sequence` was applied.
+    /// Inputs can arrive out of order, so older ones never lower it.
+    pub fn acknowledge_input(&mut self, player_id: &str, sequence: u64) -> Result<(), WorldError> {
+        let player = self
+            .players
+            .get_mut(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        player.last_input = player.last_input.max(sequence);
+        Ok(())
+    }
+
+    /// Claims `resource_id` for `player_id` and starts gathering it. The
+    /// check and the claim happen under one `&mut self`, so two players can
+    /// never both pass. Claiming a node the player already holds is a no-op.
+    pub fn start_gathering(
+        &mut self,
+        player_id: &str,
+        resource_id: &str,
+    ) -> Result<GatherLease, WorldError> {
+        self.touch(player_id)?;
+        let player = self
+            .players
+            .get(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        let node = self
+            .nodes
+            .get(resource_id)
+            .ok_or_else(|| WorldError::UnknownResource(resource_id.to_string()))?;
+        match &node.state {
+            NodeState::Available => {}
+            NodeState::Gathering {
+                player_id: holder_id,
+                player_name: holder_name,
+                ..
+            } => {
+                let lease = GatherLease {
+                    resource_id: resource_id.to_string(),
+                    holder_id: holder_id.clone(),
+                    holder_name: holder_name.clone(),
+                };
+                if holder_id == player_id {
+                    return Ok(lease);
+                }
+                return Err(WorldError::AlreadyClaimed(lease));
+            }
+            NodeState::Refilling { .. } => {
+                return Err(WorldError::NotAvailable(resource_id.to_string()))
+            }
+        }
+        let distance = player.position.distance_to(node.position);
+        let reach = tools::gather_stats(&self.tools, &player.items).gather_distance;
+        if distance > reach {
+            return Err(WorldError::OutOfRange {
+                resource_id: resource_id.to_string(),
+                distance,
+                reach,
+            });
+        }
+        if inventory::room_for(&player.inventory, node.rarity) == 0 {
+            return Err(WorldError::InventoryFull {
+                rarity: node.rarity,
+            });
+        }
+
+        let lease = GatherLease {
+            resource_id: resource_id.to_string(),
+            holder_id: player_id.to_string(),
+            holder_name: player.display_name.clone(),
+        };
+
+        // Switching nodes drops the previous one.
+        self.stop_gathering(player_id);
+        if let Some(node) = self.nodes.get_mut(resource_id) {
+            node.state = NodeState::Gathering {
+                player_id: player_id.to_string(),
+                player_name: lease.holder_name.clone(),
+                progress: 0.0,
+            };
+        }
+        if let Some(player) = self.players.get_mut(player_id) {
+            player.target = None;
+            player.gathering = Some(resource_id.to_string());
+        }
+        Ok(lease)
+    }
+
+    pub fn stop_gathering(&mut self, player_id: &str) {
+        let Some(player) = self.players.get_mut(player_id) else {
+            return;
+        };
+        let Some(resource_id) = player.gathering.take() else {
+            return;
+        };
+        if let Some(node) = self.nodes.get_mut(&resource_id) {
+            node.state = NodeState::Available;
+        }
+    }
+
+    /// Advances gather and refill timers by `delta_time` seconds.
+    pub fn update(&mut self, delta_time: f64) -> Vec<WorldEvent> {
+        let mut events = Vec::new();
+        self.time += delta_time;
+
+        for node in self.nodes.values_mut() {
+            if let NodeState::Refilling { progress } = &mut node.state {
+                *progress += delta_time;
+                if *progress >= node.refill_time {
+                    node.state = NodeState::Available;
+                    events.push(WorldEvent::Refilled {
+                        resource_id: node.id.clone(),
+                    });
+                }
+            }
+        }
+
+        for player in self.players.values_mut() {
+            if player.target.is_none() {
+                continue;
+            }
+            let mut motion = player.motion();
+            if motion.advance(delta_time) {
+                events.push(WorldEvent::Arrived {
+                    player_id: player.id.clone(),
+                });
+            }
+            player.set_motion(motion);
+            player.position_time = self.time;
+            self.player_index.insert(player.id.clone(), player.position);
+        }
+
+        for player in self.players.values_mut() {
+            let Some(resource_id) = player.gathering.clone() else {
+                continue;
+            };
+            let Some(node) = self.nodes.get_mut(&resource_id) else {
+                player.gathering = None;
+                continue;
+            };
+
+            if self.time - player.last_seen > LEASE_TIMEOUT {
+                node.state = NodeState::Available;
+                player.gathering = None;
+                events.push(WorldEvent::LeaseExpired {
+                    player_id: player.id.clone(),
+                    resource_id,
+                });
+                continue;
+            }
+
+            let stats = tools::gather_stats(&self.tools, &player.items);
+            if player.position.distance_to(node.position) > stats.gather_distance {
+                node.state = NodeState::Available;
+                player.gathering = None;
+                events.push(WorldEvent::GatherCancelled {
+                    player_id: player.id.clone(),
+                    resource_id,
+                });
+                continue;
+            }
+
+            let NodeState::Gathering { progress, .. } = &mut node.state else {
+                player.gathering = None;
+                continue;
+            };
+            *progress += delta_time * stats.gather_speed;
+            if *progress >= node.gather_time {
+                // The inventory may have been replaced mid-gather, e.g. by
+                // loading a save.
+                if inventory::room_for(&player.inventory, node.rarity) == 0 {
+                    node.state = NodeState::Available;
+                    player.gathering = None;
+                    events.push(WorldEvent::GatherCancelled {
+                        player_id: player.id.clone(),
+                        resource_id,
+                    });
+                    continue;
+                }
+                let mut amount = 1;
+                if inventory::room_for(&player.inventory, node.rarity) > 1
+                    && self.rng.range(0.0, 1.0) < stats.extra_yield_chance
+                {
+                    amount += 1;
+                }
+                node.state = NodeState::Refilling { progress: 0.0 };
+                player.gathering = None;
+                *player.inventory.entry(node.rarity).or_insert(0) += amount;
+                *player.gathered.entry(node.rarity).or_insert(0) += amount;
+                events.push(WorldEvent::Gathered {
+                    player_id: player.id.clone(),
+                    resource_id,
+                    rarity: node.rarity,
+                    amount,
+                });
+            }
+        }
+
+        events
+    }
+
+    /// Runs `update` and collects the resulting delta.
+    pub fn step(&mut self, tick: u64, delta_time: f64) -> WorldDelta {
+        self.tick = tick;
+        let events = self.update(delta_time);
+        let resource_ids: Vec<&str> = events.iter().filter_map(WorldEvent::resource_id).collect();
+        let player_ids: Vec<&str> = events.iter().filter_map(WorldEvent::player_id).collect();
+
+        let resources = self
+            .nodes
+            .values()
+            .filter(|node| !node.is_available() || resource_ids.contains(&node.id.as_str()))
+            .cloned()
+            .collect();
+        let moved = std::mem::take(&mut self.moved);
+        let players = self
+            .players
+            .values()
+            .filter(|player| {
+                player.target.is_some()
+                    || player_ids.contains(&player.id.as_str())
+                    || moved.contains(&player.id)
+            })
+            .cloned()
+            .collect();
+        WorldDelta {
+            tick,
+            events,
+            resources,
+            players,
+        }
+    }
+
+    fn nearby(&self, resource_ids: Vec<&String>, player_ids: Vec<&String>) -> Nearby {
+        Nearby {
+            resources: resource_ids
+                .into_iter()
+                .filter_map(|id| self.nodes.get(id))
+                .cloned()
+                .collect(),
+            players: player_ids
+                .into_iter()
+                .filter_map(|id| self.players.get(id))
+                .cloned()
+                .collect(),
+        }
+    }
+
+    /// Nodes and players within `radius` of `center`, nearest first.
+    pub fn query_radius(&self, center: Vector2, radius: f64) -> Nearby {
+        fn ids(index: &SpatialGrid<String>, center: Vector2, radius: f64) -> Vec<&String> {
+            index
+                .query_radius(center, radius)
+                .into_iter()
+                .map(|(id, _)| id)
+                .collect()
+        }
+        self.nearby(
+            ids(&self.node_index, center, radius),
+            ids(&self.player_index, center, radius),
+        )
+    }
+
+    /// Nodes and players inside the box from `min` to `max`, e.g. a
+    /// viewport.
+    pub fn query_rect(&self, min: Vector2, max: Vector2) -> Nearby {
+        self.nearby(
+            self.node_index.query_rect(min, max),
+            self.player_index.query_rect(min, max),
+        )
+    }
+
+    /// Closest node of `rarity` to `from`, optionally only among nodes that
+    /// can be gathered right now.
+    pub fn nearest_of_tier(
+        &self,
+        from: Vector2,
+        rarity: u8,
+        available_only: bool,
+    ) -> Option<&ResourceNode> {
+        let (id, _) = self.node_index.nearest(from, |id| {
+            self.nodes.get(id).is_some_and(|node| {
+                node.rarity == rarity && (!available_only || node.is_available())
+            })
+        })?;
+        self.nodes.get(id)
+    }
+
+    /// Available nodes within the player's gather reach, nearest first.
+    pub fn gatherable_resources(&self, player_id: &str) -> Result<Vec<ResourceNode>, WorldError> {
+        let player = self
+            .players
+            .get(player_id)
+            .ok_or_else(|| WorldError::UnknownPlayer(player_id.to_string()))?;
+        let reach = tools::gather_stats(&self.tools, &player.items).gather_distance;
+        Ok(self
+            .query_radius(player.position, reach)
+            .resources
+            .into_iter()
+            .filter(ResourceNode::is_available)
+            .collect())
+    }
+
+    pub fn snapshot(&self) -> WorldSnapshot {
+        WorldSnapshot {
+            base: self.base,
+            resources: self.nodes.values().cloned().collect(),
+            players: self.players.values().cloned().collect(),
+        }
+    }
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn join_world(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    display_name: String,
+    x: f64,
+    y: f64,
+) {
+    world
+        .lock()
+        .unwrap()
+        .join(&player_id, &display_name, Vector2::new(x, y));
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn leave_world(world: State<'_, Mutex<World>>, player_id: String) {
+    world.lock().unwrap().leave(&player_id);
+}
+
+/// Reports where a client walking on its own is. Must be sent at least every
+/// `MAX_POSITION_INTERVAL` while walking; clients that let the backend move
+/// them use `move_player` instead.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn set_player_position(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    x: f64,
+    y: f64,
+) -> Result<(), WorldError> {
+    world
+        .lock()
+        .unwrap()
+        .set_player_position(&player_id, Vector2::new(x, y))
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn move_player(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    x: f64,
+    y: f64,
+) -> Result<(), WorldError> {
+    world
+        .lock()
+        .unwrap()
+        .set_move_target(&player_id, Vector2::new(x, y))
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn start_gathering(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    resource_id: String,
+) -> Result<GatherLease, WorldError> {
+    world
+        .lock()
+        .unwrap()
+        .start_gathering(&player_id, &resource_id)
+}
+
+/// Keeps the player's gather lease alive while it is otherwise idle.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn heartbeat(world: State<'_, Mutex<World>>, player_id: String) -> Result<(), WorldError> {
+    world.lock().unwrap().touch(&player_id)
+}
+
+/// Remaining carry room overall and per tier.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn get_capacity(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+) -> Result<Capacity, WorldError> {
+    world.lock().unwrap().capacity(&player_id)
+}
+
+/// Deposits carried resources at the base, see `World::deposit`.
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn deposit(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    rarity: Option<u8>,
+    count: Option<u32>,
+) -> Result<PlayerState, WorldError> {
+    world.lock().unwrap().deposit(&player_id, rarity, count)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn withdraw(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+    rarity: u8,
+    count: u32,
+) -> Result<PlayerState, WorldError> {
+    world.lock().unwrap().withdraw(&player_id, rarity, count)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn bank_contents(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+) -> Result<Inventory, WorldError> {
+    world.lock().unwrap().bank_contents(&player_id)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn query_radius(world: State<'_, Mutex<World>>, center: Vector2, radius: f64) -> Nearby {
+    world.lock().unwrap().query_radius(center, radius)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn query_rect(world: State<'_, Mutex<World>>, min: Vector2, max: Vector2) -> Nearby {
+    world.lock().unwrap().query_rect(min, max)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn nearest_of_tier(
+    world: State<'_, Mutex<World>>,
+    from: Vector2,
+    rarity: u8,
+    available_only: Option<bool>,
+) -> Option<ResourceNode> {
+    world
+        .lock()
+        .unwrap()
+        .nearest_of_tier(from, rarity, available_only.unwrap_or(false))
+        .cloned()
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn gatherable_resources(
+    world: State<'_, Mutex<World>>,
+    player_id: String,
+) -> Result<Vec<ResourceNode>, WorldError> {
+    world.lock().unwrap().gatherable_resources(&player_id)
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn stop_gathering(world: State<'_, Mutex<World>>, player_id: String) {
+    world.lock().unwrap().stop_gathering(&player_id);
+}
+
+#[cfg(feature = "gui")]
+#[tauri::command]
+pub fn get_world_snapshot(world: State<'_, Mutex<World>>) -> WorldSnapshot {
+    world.lock().unwrap().snapshot()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::constants::MAX_TOTAL_SHARDS;
+    use crate::map::{Obstacle, ResourceLocation};
+
+    const NODE: &str = "resource_500_500_0";
+
+    fn world_with(locations: &[(f64, f64, u8)]) -> World {
+        let mut world = World::new();
+        world.load_map(&MapData {
+            locations: locations
+                .iter()
+                .map(|&(x, y, rarity)| ResourceLocation { x, y, rarity })
+                .collect(),
+            base: None,
+            obstacles: Vec::new(),
+        });
+        world
+    }
+
+    fn inventory(counts: &[(u8, u32)]) -> Inventory {
+        counts.iter().copied().collect()
+    }
+
+    fn node_state(world: &World, resource_id: &str) -> NodeState {
+        world.nodes[resource_id].state.clone()
+    }
+
+    #[test]
+    fn gathering_harvests_then_refills() {
+        let mut world = world_with(&[(500.0, 500.0, 0)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world.start_gathering("a", NODE).unwrap();
+
+        assert!(world.update(0.5).is_empty());
+        assert!(matches!(
+            node_state(&world, NODE),
+            NodeState::Gathering { progress, .. } if progress == 0.5
+        ));
+
+        let events = world.update(0.6);
+        assert!(matches!(
+            events.as_slice(),
+            [WorldEvent::Gathered {
+                rarity: 0,
+                amount: 1,
+                ..
+            }]
+        ));
+        assert_eq!(world.player("a").unwrap().inventory, inventory(&[(0, 1)]));
+        assert!(world.player("a").unwrap().gathering.is_none());
+        assert!(matches!(
+            node_state(&world, NODE),
+            NodeState::Refilling { .. }
+        ));
+        assert!(matches!(
+            world.start_gathering("a", NODE),
+            Err(WorldError::NotAvailable(_))
+        ));
+
+        let refill_time = world.nodes[NODE].refill_time;
+        let events = world.update(refill_time);
+        assert!(matches!(
+            events.as_slice(),
+            [WorldEvent::Refilled { resource_id }] if resource_id == NODE
+        ));
+        assert!(world.nodes[NODE].is_available());
+        assert!(world.start_gathering("a", NODE).is_ok());
+    }
+
+    /// A world with one common node where player `a` owns a tool.
+    fn world_with_tool(gather_speed: f64, range_bonus: f64, extra_yield_chance: f64) -> World {
+        let mut world = world_with(&[(500.0, 500.0, 0)]);
+        let tool = tools::ToolDefinition {
+            id: "tool".to_string(),
+            name: "Tool".to_string(),
+            gather_speed,
+            range_bonus,
+            extra_yield_chance,
+        };
+        world.set_tools(ToolTable::from([(tool.id.clone(), tool)]));
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world
+            .players
+            .get_mut("a")
+            .unwrap()
+            .items
+            .insert("tool".to_string(), 1);
+        world
+    }
+
+    #[test]
+    fn tools_speed_up_gathering() {
+        let mut world = world_with_tool(2.0, 0.0, 0.0);
+        world.start_gathering("a", NODE).unwrap();
+        assert!(world.update(0.4).is_empty());
+        assert!(matches!(
+            world.update(0.2).as_slice(),
+            [WorldEvent::Gathered { amount: 1, .. }]
+        ));
+
+        // Tools only count while owned.
+        let mut world = world_with_tool(2.0, 0.0, 0.0);
+        world
+            .players
+            .get_mut("a")
+            .unwrap()
+            .items
+            .insert("tool".to_string(), 0);
+        world.start_gathering("a", NODE).unwrap();
+        assert!(world.update(0.5).is_empty());
+    }
+
+    #[test]
+    fn tools_extend_the_gather_distance() {
+        let mut world = world_with_tool(1.0, 50.0, 0.0);
+        let items = world.players["a"].items.clone();
+        for (id, x) in [("b", 640.0), ("c", 640.0), ("d", 660.0)] {
+            world.join(id, id, Vector2::new(x, 500.0));
+        }
+        world.players.get_mut("b").unwrap().items = items.clone();
+        world.players.get_mut("d").unwrap().items = items;
+
+        assert!(matches!(
+            world.start_gathering("c", NODE),
+            Err(WorldError::OutOfRange { reach, .. }) if reach == RESOURCE_GATHER_DISTANCE
+        ));
+        assert!(matches!(
+            world.start_gathering("d", NODE),
+            Err(WorldError::OutOfRange { reach, .. }) if reach == RESOURCE_GATHER_DISTANCE + 50.0
+        ));
+        world.start_gathering("b", NODE).unwrap();
+        assert!(matches!(
+            world.update(1.0).as_slice(),
+            [WorldEvent::Gathered { .. }]
+        ));
+    }
+
+    #[test]
+    fn tools_can_yield_extra_resources() {
+        let mut world = world_with_tool(1.0, 0.0, 1.0);
+        world.start_gathering("a", NODE).unwrap();
+        assert!(matches!(
+            world.update(1.0).as_slice(),
+            [WorldEvent::Gathered { amount: 2, .. }]
+        ));
+        assert_eq!(world.player("a").unwrap().inventory, inventory(&[(0, 2)]));
+        assert_eq!(world.player("a").unwrap().gathered[&0], 2);
+    }
+
+    #[test]
+    fn walking_out_of_range_cancels_gathering() {
+        let mut world = world_with(&[(500.0, 500.0, 0)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world.start_gathering("a", NODE).unwrap();
+        world.players.get_mut("a").unwrap().position = Vector2::new(900.0, 500.0);
+
+        let events = world.update(0.1);
+        assert!(matches!(
+            events.as_slice(),
+            [WorldEvent::GatherCancelled { .. }]
+        ));
+        assert!(world.nodes[NODE].is_available());
+    }
+
+    #[test]
+    fn silent_players_lose_their_lease() {
+        let mut world = world_with(&[(500.0, 500.0, 4)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world.start_gathering("a", "resource_500_500_4").unwrap();
+
+        let events = world.update(LEASE_TIMEOUT + 0.1);
+        assert!(matches!(
+            events.as_slice(),
+            [WorldEvent::LeaseExpired { .. }]
+        ));
+        assert!(world.nodes["resource_500_500_4"].is_available());
+    }
+
+    #[test]
+    fn claimed_nodes_cannot_be_taken() {
+        let mut world = world_with(&[(500.0, 500.0, 0)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world.join("b", "B", Vector2::new(480.0, 500.0));
+        world.start_gathering("a", NODE).unwrap();
+
+        match world.start_gathering("b", NODE) {
+            Err(WorldError::AlreadyClaimed(lease)) => {
+                assert_eq!(lease.holder_id, "a");
+                assert_eq!(lease.holder_name, "A");
+            }
+            _ => panic!("expected the node to be claimed by a"),
+        }
+        // Claiming again keeps the lease and its progress.
+        world.update(0.5);
+        assert_eq!(world.start_gathering("a", NODE).unwrap().holder_id, "a");
+        assert!(matches!(
+            node_state(&world, NODE),
+            NodeState::Gathering { progress, .. } if progress == 0.5
+        ));
+
+        world.stop_gathering("a");
+        assert_eq!(world.start_gathering("b", NODE).unwrap().holder_id, "b");
+    }
+
+    #[test]
+    fn switching_nodes_releases_the_previous_one() {
+        let mut world = world_with(&[(500.0, 500.0, 0), (540.0, 500.0, 0)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world.start_gathering("a", NODE).unwrap();
+        world.start_gathering("a", "resource_540_500_0").unwrap();
+        assert!(world.nodes[NODE].is_available());
+        assert_eq!(
+            world.player("a").unwrap().gathering.as_deref(),
+            Some("resource_540_500_0")
+        );
+    }
+
+    #[test]
+    fn out_of_range_nodes_cannot_be_claimed() {
+        let mut world = world_with(&[(500.0, 500.0, 0)]);
+        world.join("a", "A", Vector2::new(800.0, 500.0));
+        assert!(matches!(
+            world.start_gathering("a", NODE),
+            Err(WorldError::OutOfRange { .. })
+        ));
+        assert!(world.nodes[NODE].is_available());
+    }
+
+    #[test]
+    fn full_tiers_cannot_be_gathered() {
+        let mut world = world_with(&[(500.0, 500.0, 4)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        let cap = tiers::tier(4).unwrap().carry_cap.unwrap();
+        world.players.get_mut("a").unwrap().inventory = inventory(&[(4, cap)]);
+        assert!(matches!(
+            world.start_gathering("a", "resource_500_500_4"),
+            Err(WorldError::InventoryFull { rarity: 4 })
+        ));
+    }
+
+    #[test]
+    fn the_shard_budget_limits_gathering() {
+        let mut world = world_with(&[(500.0, 500.0, 0)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        let budget = MAX_TOTAL_SHARDS / tiers::shard_value(0);
+        world.players.get_mut("a").unwrap().inventory = inventory(&[(0, budget)]);
+        assert!(matches!(
+            world.start_gathering("a", NODE),
+            Err(WorldError::InventoryFull { rarity: 0 })
+        ));
+        assert_eq!(world.capacity("a").unwrap().remaining_shards, 0);
+    }
+
+    #[test]
+    fn a_full_inventory_cancels_the_harvest() {
+        let mut world = world_with(&[(500.0, 500.0, 4)]);
+        world.join("a", "A", Vector2::new(520.0, 500.0));
+        world.start_gathering("a", "resource_500_500_4").unwrap();
+        let cap = tiers::tier(4).unwrap().carry_cap.unwrap();
+        world.players.get_mut("a").unwrap().inventory = inventory(&[(4, cap)]);
+
+        let gather_time = world.nodes["resource_500_500_4"].gather_time;
+        let events = world.update(gather_time);
+        assert!(matches!(
+            events.as_slice(),
+            [WorldEvent::GatherCancelled { .. }]
+        ));
+        assert_eq!(world.player("a").unwrap().inventory, inventory(&[(4, cap)]));
+    }
+
+    #[test]
+    fn standing_still_does_not_allow_a_jump() {
+        let mut world = world_with(&[]);
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        world.update(30.0);
+        assert!(matches!(
+            world.set_player_position("a", Vector2::new(1500.0, 500.0)),
+            Err(WorldError::ImpossibleMove { .. })
+        ));
+        assert_eq!(
+            world.player("a").unwrap().position,
+            Vector2::new(500.0, 500.0)
+        );
+
+        world
+            .set_player_position("a", Vector2::new(540.0, 500.0))
+            .unwrap();
+        assert_eq!(
+            world.player("a").unwrap().position,
+            Vector2::new(540.0, 500.0)
+        );
+    }
+
+    #[test]
+    fn repeated_reports_cannot_add_up_to_a_jump() {
+        let mut world = world_with(&[]);
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        world.update(1.0);
+        for step in 1..=40 {
+            let _ = world.set_player_position("a", Vector2::new(500.0 + 45.0 * step as f64, 500.0));
+        }
+        assert!(world.player("a").unwrap().position.x <= 500.0 + MAX_POSITION_BUDGET);
+    }
+
+    #[test]
+    fn reports_at_walking_speed_are_accepted() {
+        let mut world = world_with(&[]);
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        for step in 1..=40 {
+            world.update(0.1);
+            world
+                .set_player_position("a", Vector2::new(500.0 + 20.0 * step as f64, 500.0))
+                .unwrap();
+        }
+        assert_eq!(
+            world.player("a").unwrap().position,
+            Vector2::new(1300.0, 500.0)
+        );
+
+        // Missing the report interval loses the walked distance.
+        world.update(1.0);
+        assert!(matches!(
+            world.set_player_position("a", Vector2::new(1500.0, 500.0)),
+            Err(WorldError::ImpossibleMove { .. })
+        ));
+    }
+
+    #[test]
+    fn reported_positions_respect_obstacles() {
+        let mut world = World::new();
+        world.load_map(&MapData {
+            locations: Vec::new(),
+            base: None,
+            obstacles: vec![Obstacle {
+                x: 510.0,
+                y: 400.0,
+                width: 20.0,
+                height: 200.0,
+            }],
+        });
+        world.join("a", "A", Vector2::new(500.0, 500.0));
+        world.update(1.0);
+        assert!(matches!(
+            world.set_player_position("a", Vector2::new(520.0, 500.0)),
+            Err(WorldError::NoPath { .. })
+        ));
+        assert!(matches!(
+            world.set_player_position("a", Vector2::new(540.0, 500.0)),
+            Err(WorldError::NoPath { .. })
+        ));
+        world
+            .set_player_position("a", Vector2::new(500.0, 540.0))
+            .unwrap();
+    }
+
+    #[test]
+    fn the_bank_stores_and_returns_resources() {
+        let mut world = world_with(&[]);
+        world.join("a", "A", world.base());
+        world.players.get_mut("a").unwrap().inventory = inventory(&[(0, 10), (1, 2)]);
+
+        world.deposit("a", Some(0), Some(4)).unwrap();
+        let player = world.player("a").unwrap();
+        assert_eq!(player.inventory, inventory(&[(0, 6), (1, 2)]));
+        assert_eq!(player.bank, inventory(&[(0, 4)]));
+
+        world.deposit("a", None, None).unwrap();
+        let player = world.player("a").unwrap();
+        assert!(player.inventory.is_empty());
+        assert_eq!(player.bank, inventory(&[(0, 10), (1, 2)]));
+
+        world.withdraw("a", 1, 2).unwrap();
+        assert_eq!(world.bank_contents("a").unwrap(), inventory(&[(0, 10)]));
+        assert_eq!(world.player("a").unwrap().inventory, inventory(&[(1, 2)]));
+    }
+
+    #[test]
+    fn the_bank_rejects_what_it_cannot_do() {
+        let mut world = world_with(&[]);
+        world.join("a", "A", world.base());
+        world.players.get_mut("a").unwrap().inventory = inventory(&[(0, 3)]);
+
+        assert!(matches!(
+            world.deposit("a", Some(0), Some(4)),
+            Err(WorldError::NotEnough {
+                rarity: 0,
+                requested: 4,
+                available: 3
+            })
+        ));
+        assert!(matches!(
+            world.withdraw("a", 0, 1),
+            Err(WorldError::NotEnough { available: 0, .. })
+        ));
+
+        let cap = tiers::tier(4).unwrap().carry_cap.unwrap();
+        world.players.get_mut("a").unwrap().bank = inventory(&[(4, cap + 1)]);
+        assert!(matches!(
+            world.withdraw("a", 4, cap + 1),
+            Err(WorldError::InventoryFull { rarity: 4 })
+        ));
+        assert_eq!(
+            world.bank_contents("a").unwrap(),
+            inventory(&[(4, cap + 1)])
+        );
+
+        world.players.get_mut("a").unwrap().position = world.base() + Vector2::new(200.0, 0.0);
+        assert!(matches!(
+            world.deposit("a", None, None),
+            Err(WorldError::TooFarFromBase { .. })
+        ));
+        assert_eq!(world.player("a").unwrap().inventory, inventory(&[(0, 3)]));
+    }
+}
@@ -70,6 +70,7 @@ export const ASSET_PATHS = {
     RESOURCE_T3: "/assets/resources/t3.png",
     RESOURCE_T4: "/assets/resources/t4.png",
     RESOURCE_T5: "/assets/resources/t5.png",
+    RESOURCE_T6: "/assets/resources/t6.png",
 };
 
 export const DEFAULT_MAP_ID = "f0d38487-e743-432d-9a74-65dd6b219a26"
@@ -28,6 +28,9 @@ resourceT4Image.src = ASSET_PATHS.RESOURCE_T4;
 const resourceT5Image = new Image();
 resourceT5Image.src = ASSET_PATHS.RESOURCE_T5;
 
+const resourceT6Image = new Image();
+resourceT6Image.src = ASSET_PATHS.RESOURCE_T6;
+
 class Resource {
   position: Vector2;
   rarity: ResourceRarity;
@@ -75,6 +78,10 @@ class Resource {
         this.image = resourceT5Image;
         this.shardValue = RESOURCE_SHARD_VALUES.LEGENDARY;
         break;
+      case ResourceRarity.MYTHICAL:
+        this.image = resourceT6Image;
+        this.shardValue = RESOURCE_SHARD_VALUES.MYTHICAL;
+        break;
       default:
         this.image = resourceT1Image;
         this.shardValue = RESOURCE_SHARD_VALUES.COMMON;
@@ -385,4 +392,4 @@ class Resource {
   }
 }
 
-export { Resource, resourceT1Image, resourceT2Image, resourceT3Image, resourceT4Image, resourceT5Image };
+export { Resource, resourceT1Image, resourceT2Image, resourceT3Image, resourceT4Image, resourceT5Image, resourceT6Image };
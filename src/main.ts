@@ -1,5 +1,5 @@
 import { invoke } from '@tauri-apps/api/core';
-import { sleep } from './utils/helpers';
+import { listen } from '@tauri-apps/api/event';
 import {
   CANVAS_WIDTH,
   CANVAS_HEIGHT,
@@ -30,11 +30,22 @@ import { Camera } from './lib/camera';
 import { Player } from './lib/player';
 import { getTauriVersion } from '@tauri-apps/api/app';
 
-async function setup() {
-  await sleep(3);
+// The frontend task is done once the game has loaded its translations and textures
+async function completeFrontendTask(game: Game) {
+  await game.ready;
   await invoke('set_complete', { task: 'frontend' });
 }
 
+async function setup(game: Game) {
+  // The splashscreen's Retry button re-runs failed frontend tasks through this event
+  await listen<string>('startup-retry', event => {
+    if (event.payload === 'frontend') {
+      completeFrontendTask(game);
+    }
+  });
+  await completeFrontendTask(game);
+}
+
 // Initialize titlebar controls for Tauri
 (async function initializeTauriControls() {
   try {
@@ -65,19 +76,19 @@ async function setup() {
 })();
 
 window.addEventListener('DOMContentLoaded', async () => {
+  // Initialize the game regardless of environment
+  const game = new Game();
+
   try {
     // Check if running in Tauri by trying to get the Tauri version
     const tauriVersion = await getTauriVersion();
     if (tauriVersion) {
-      setup();
+      setup(game);
     }
   } catch (e) {
     // Not running in Tauri
     console.log('Not running in Tauri environment');
   }
-
-  // Initialize the game regardless of environment
-  new Game();
 });
 
 // Generate a unique player ID
@@ -394,6 +405,8 @@ class Game {
   showSettingsPopup: boolean = false;
   popupScrollPosition: number = 0;
   languageManager: LanguageManager;
+  // Resolves once translations and textures are loaded and the game loop runs
+  ready: Promise<void>;
 
   constructor() {
     this.canvas = document.getElementById('game-canvas') as HTMLCanvasElement;
@@ -417,7 +430,7 @@ class Game {
     this.setupEventListeners();
 
     // Initialize the game
-    this.init();
+    this.ready = this.init();
     
     // If this is a new player, show the display name dialog after a short delay
     // to ensure the game is fully loaded
@@ -1380,6 +1393,7 @@ class Game {
       t('game.resources.rare'),
       t('game.resources.epic'),
       t('game.resources.legendary'),
+      t('game.resources.mythical'),
       '',
       t('game.tips.title'),
       t('game.tips.noMovement'),
@@ -13,6 +13,8 @@ export function getResourceColor(rarity: ResourceRarity): string {
       return RESOURCE_COLORS.EPIC;
     case ResourceRarity.LEGENDARY:
       return RESOURCE_COLORS.LEGENDARY;
+    case ResourceRarity.MYTHICAL:
+      return RESOURCE_COLORS.MYTHICAL;
     default:
       return RESOURCE_COLORS.COMMON;
   }
@@ -3,5 +3,6 @@ export enum ResourceRarity {
   UNCOMMON = 1,
   RARE = 2,
   EPIC = 3,
-  LEGENDARY = 4
+  LEGENDARY = 4,
+  MYTHICAL = 5
 }
\ No newline at end of file